@@ -0,0 +1,10 @@
+// Feeds arbitrary list pages to the layout detection and the list parser.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(body) = std::str::from_utf8(data) {
+        anime_crawler::requester::fuzz_list_page(body);
+    }
+});
@@ -1,28 +1,120 @@
-use chrono::{NaiveDate, Utc};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use chrono::prelude::*;
-use crate::requester;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use crate::error::CrawlerError;
+use crate::intern;
+use crate::titles::TitleLanguage;
 
-#[derive(Debug)]
+/// Section of a user's list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListStatus {
+    #[default]
+    Watching,
+    Completed,
+    OnHold,
+    Dropped,
+    PlanToWatch,
+    All,
+}
+
+impl ListStatus {
+    /// number of the section on the list page, as in `?status=1`.
+    pub fn number(self) -> i32 {
+        match self {
+            ListStatus::Watching => 1,
+            ListStatus::Completed => 2,
+            ListStatus::OnHold => 3,
+            ListStatus::Dropped => 4,
+            ListStatus::PlanToWatch => 6,
+            ListStatus::All => 7,
+        }
+    }
+
+    pub fn from_number(number: i32) -> Option<Self> {
+        let status = match number {
+            1 => ListStatus::Watching,
+            2 => ListStatus::Completed,
+            3 => ListStatus::OnHold,
+            4 => ListStatus::Dropped,
+            6 => ListStatus::PlanToWatch,
+            7 => ListStatus::All,
+            _ => return None,
+        };
+        Some(status)
+    }
+
+    /// name of the section in the MAL API, None for the whole list.
+    pub fn api_name(self) -> Option<&'static str> {
+        match self {
+            ListStatus::Watching => Some("watching"),
+            ListStatus::Completed => Some("completed"),
+            ListStatus::OnHold => Some("on_hold"),
+            ListStatus::Dropped => Some("dropped"),
+            ListStatus::PlanToWatch => Some("plan_to_watch"),
+            ListStatus::All => None,
+        }
+    }
+
+    pub fn from_api_name(name: &str) -> Option<Self> {
+        let status = match name {
+            "watching" => ListStatus::Watching,
+            "completed" => ListStatus::Completed,
+            "on_hold" => ListStatus::OnHold,
+            "dropped" => ListStatus::Dropped,
+            "plan_to_watch" => ListStatus::PlanToWatch,
+            _ => return None,
+        };
+        Some(status)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserAttributes {
     pub uname: String,
+    // section of the list that gets crawled, the watching list by default.
+    #[serde(default)]
+    pub list_status: ListStatus,
     
     // users should modify here according to their
     // profiles. Apperantly, a user can change their
-    // time format.
-    // TODO: Fetch time format from user profile, if possible.
+    // time format. Left empty, it is guessed from the
+    // start dates of the list, see infer_date_format.
 
+    #[serde(default)]
     pub date_format: String,
-    pub date_format_backup: String
+    #[serde(default)]
+    pub date_format_backup: String,
+    // title the entries of the list are shown with, the romaji one by default.
+    #[serde(default)]
+    pub title_language: TitleLanguage,
 }
 
 impl UserAttributes {
     pub fn new(uname: String) -> Self {
-        UserAttributes { uname, date_format: String::new(), 
-                         date_format_backup: String::new() }
+        UserAttributes { uname, list_status: ListStatus::default(), date_format: String::new(), 
+                         date_format_backup: String::new(), title_language: TitleLanguage::default() }
+    }
+
+    /// false until a date format is set, the crawl then guesses it.
+    pub fn has_date_format(&self) -> bool {
+        !self.date_format.is_empty()
     }
 
-    pub fn set_date_format(&mut self, d_format: String) {
-        match parse_i32(&d_format) {
+    /// set the section of the list to crawl from its number on the list
+    /// page, e.g. "2" for the completed list.
+    pub fn set_list_status(&mut self, status: &str) -> Result<(), CrawlerError> {
+        self.list_status = status.parse::<i32>().ok().and_then(ListStatus::from_number)
+                                 .ok_or_else(|| CrawlerError::InvalidListStatus(String::from(status)))?;
+        Ok(())
+    }
+
+    pub fn set_date_format(&mut self, d_format: String) -> Result<(), CrawlerError> {
+        match d_format.parse::<i32>() {
             Ok(1) => {
                 self.date_format = String::from("%d-%m-%Y");
                 self.date_format_backup = String::from("%m-%d-%Y");
@@ -31,49 +123,231 @@ impl UserAttributes {
                 self.date_format = String::from("%m-%d-%Y");
                 self.date_format_backup = String::from("%d-%m-%Y");
             },
-            Ok(num) => {
-                panic!("User entered number that is out of range [1, 2]: {}", num); 
-            },
-            Err(err) => {
-                panic!("Error occured during parsing {} -> {:?}", d_format, err);
+            _ => {
+                return Err(CrawlerError::InvalidDateFormat(d_format));
             }
         };
+        Ok(())
     }
 }
 
-#[derive(Debug, Clone)]
+/// guess the date format of a list from start dates like "29-09-2023", a
+/// number above 12 can only be the day. Returns the option of
+/// set_date_format, or None if every date reads both ways.
+pub fn infer_date_format<'a, I: IntoIterator<Item = &'a str>>(start_dates: I) -> Option<&'static str> {
+    for date in start_dates {
+        let mut numbers = date.split('-').map(|number| number.parse::<u32>().ok());
+        match (numbers.next().flatten(), numbers.next().flatten()) {
+            (Some(first), Some(second)) if first > 12 && second <= 12 => return Some("1"),
+            (Some(first), Some(second)) if second > 12 && first <= 12 => return Some("2"),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// An entry of a list. It (de)serializes with the keys of the data-items
+/// payload of the list page, so entries of the payload deserialize into it;
+/// their airing day is then set from the start date, see set_start_date.
+/// What the payload does not carry keeps the keys of the fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AnimeAttributes {
     pub status                  : i32,
     pub score                   : i32,
+    #[serde(rename = "anime_id")]
     pub id                      : i32,
     pub num_watched_episodes    : i32,
+    #[serde(rename = "anime_num_episodes")]
     pub num_episodes            : i32,
+    #[serde(skip)]
     current_day                 : i32,
+    #[serde(rename = "airing_day")]
     anime_airing_day            : i32,
+    #[serde(deserialize_with = "flag")]
     pub is_rewatching           : bool,
+    #[serde(rename = "anime_airing_status", serialize_with = "write_airing_status",
+            deserialize_with = "read_airing_status")]
     pub is_airing               : bool,
+    #[serde(rename = "anime_title", deserialize_with = "title")]
     pub title                   : String,
+    #[serde(rename = "anime_title_eng", deserialize_with = "title")]
     pub title_eng               : String,
+    // native title, known once the anime page is fetched.
+    pub title_jp                : String,
+    // alternative titles, e.g. read from the anime page.
+    pub synonyms                : Vec<String>,
     // day - month - year or month - day - year
-    pub start_date              : String,
+    #[serde(rename = "anime_start_date_string", deserialize_with = "start_date")]
+    pub start_date              : Arc<str>,
+    // the start date as a date, in JST.
+    pub first_aired             : Option<NaiveDate>,
+    // local time the episodes air at, known once the anime page is fetched.
+    pub airing_time             : Option<NaiveTime>,
+    // broadcast slot as announced, in JST, known once the anime page is fetched.
+    pub broadcast               : Option<BroadcastSchedule>,
+    // metadata of the anime page, known once it is fetched.
+    #[serde(deserialize_with = "names")]
+    pub genres                  : Vec<String>,
+    #[serde(alias = "anime_studios", deserialize_with = "names")]
+    pub studios                 : Vec<String>,
+    // source material, e.g. "Manga".
+    pub source                  : Option<String>,
+    // community score, score is the user's own.
+    pub mean_score              : Option<f32>,
+    // length of an episode in seconds.
+    pub episode_seconds         : Option<u32>,
 }
 
-fn parse_i32(mut value: &str) -> Result<i32, std::num::ParseIntError> {
-    value = if value.chars().last() == Some(',') {
-            &value[1..value.len()-1]
-        } else {
-            value
-    };
+/// keys AnimeAttributes reads from an entry of the list payload: the serde
+/// names of its fields, and the anime_studios alias of studios.
+pub const ENTRY_KEYS: [&str; 22] = ["status", "score", "anime_id", "num_watched_episodes", "anime_num_episodes",
+                                    "airing_day", "is_rewatching", "anime_airing_status", "anime_title",
+                                    "anime_title_eng", "title_jp", "synonyms", "anime_start_date_string",
+                                    "first_aired", "airing_time", "broadcast", "genres", "studios", "anime_studios",
+                                    "source", "mean_score", "episode_seconds"];
+
+/// MAL writes flags as 0 and 1 in some lists and as booleans in others.
+fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Bool(flag) => Ok(flag),
+        Value::Number(number) => Ok(number.as_i64() == Some(1)),
+        Value::Null => Ok(false),
+        other => Err(de::Error::custom(format!("expected a flag, found {}", other))),
+    }
+}
+
+/// titles that look like numbers, e.g. "86", are written as numbers.
+fn title<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(title) => Ok(title),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Null => Ok(String::new()),
+        other => Err(de::Error::custom(format!("expected a title, found {}", other))),
+    }
+}
+
+/// genres are written as {"id": 1, "name": "Action"} objects in the list.
+fn names<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let names = Option::<Vec<Value>>::deserialize(deserializer)?.unwrap_or_default();
+    names.into_iter().map(|name| match name {
+        Value::String(name) => Ok(name),
+        Value::Object(mut fields) => match fields.remove("name") {
+            Some(Value::String(name)) => Ok(name),
+            _ => Err(de::Error::custom("expected a name")),
+        },
+        other => Err(de::Error::custom(format!("expected a name, found {}", other))),
+    }).collect()
+}
+
+/// start dates are null for anime without one, they are interned as the
+/// list repeats them.
+fn start_date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Arc<str>, D::Error> {
+    let date = Option::<String>::deserialize(deserializer)?;
+    Ok(intern::global().intern(date.as_deref().unwrap_or("")))
+}
+
+/// the airing status is 1 while the anime airs, 2 once it finished and 3
+/// before it started. Entries that are not airing are written as finished.
+fn read_airing_status<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(Option::<i32>::deserialize(deserializer)? == Some(1))
+}
+
+fn write_airing_status<S: Serializer>(is_airing: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i32(if *is_airing { 1 } else { 2 })
+}
+
+/// weekly broadcast slot of an anime, e.g. "Saturdays at 23:00 (JST)" on
+/// its page, in the timezone it is announced in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BroadcastSchedule {
+    pub weekday: Weekday,
+    pub hour: u32,
+    pub minute: u32,
+    /// abbreviation of the timezone, e.g. "JST".
+    pub tz: String,
+}
+
+impl BroadcastSchedule {
+    /// a slot in JST, the timezone MAL announces broadcasts in.
+    pub fn jst(weekday: Weekday, time: NaiveTime) -> Self {
+        BroadcastSchedule { weekday, hour: time.hour(), minute: time.minute(), tz: String::from("JST") }
+    }
+
+    /// parse the broadcast as shown on an anime page, None for "Unknown"
+    /// and other broadcasts without a weekly slot.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut words = value.split_whitespace();
+        let day = words.next()?;
+        let weekday = day.strip_suffix('s').unwrap_or(day).parse::<Weekday>().ok()?;
+        if words.next()? != "at" {
+            return None;
+        }
+        let (hour, minute) = words.next()?.split_once(':')?;
+        let time = NaiveTime::from_hms_opt(hour.parse().ok()?, minute.parse().ok()?, 0)?;
+        let tz = words.next().map_or("JST", |tz| tz.trim_start_matches('(').trim_end_matches(')'));
+        Some(BroadcastSchedule { tz: String::from(tz), ..BroadcastSchedule::jst(weekday, time) })
+    }
+
+    pub fn time(&self) -> Option<NaiveTime> {
+        NaiveTime::from_hms_opt(self.hour, self.minute, 0)
+    }
 
-    value.parse::<i32>()
+    /// offset of the timezone from UTC, None if it is not one MAL uses.
+    pub fn utc_offset(&self) -> Option<FixedOffset> {
+        match self.tz.as_str() {
+            "JST" => FixedOffset::east_opt(9 * 3600),
+            "UTC" | "GMT" => FixedOffset::east_opt(0),
+            _ => None,
+        }
+    }
+
+    /// the next broadcast after `now`.
+    pub fn next_airing(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let now = now.with_timezone(&self.utc_offset()?);
+        let days_ahead = (self.weekday.num_days_from_monday() as i64
+                          - now.weekday().num_days_from_monday() as i64).rem_euclid(7);
+        let date = now.date_naive() + chrono::Duration::days(days_ahead);
+        let mut next = date.and_time(self.time()?).and_local_timezone(now.timezone()).single()?;
+        if next <= now {
+            next += chrono::Duration::days(7);
+        }
+        Some(next.with_timezone(&Utc))
+    }
+}
+
+impl fmt::Display for BroadcastSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let day = match self.weekday {
+            Weekday::Mon => "Mondays",
+            Weekday::Tue => "Tuesdays",
+            Weekday::Wed => "Wednesdays",
+            Weekday::Thu => "Thursdays",
+            Weekday::Fri => "Fridays",
+            Weekday::Sat => "Saturdays",
+            Weekday::Sun => "Sundays",
+        };
+        write!(f, "{} at {:02}:{:02} ({})", day, self.hour, self.minute, self.tz)
+    }
+}
+
+fn parse_date(value: &str, format: &str) -> Result<NaiveDate, CrawlerError> {
+    NaiveDate::parse_from_str(value, format).map_err(|source| CrawlerError::ParseDate {
+        value: String::from(value), source
+    })
 }
 
 impl Default for AnimeAttributes {
     fn default() -> Self {
         AnimeAttributes { status: 0, score: 0, id: 0, num_watched_episodes: 0,
                           num_episodes: 0, is_rewatching: false, is_airing: false,
-                          title: String::new(), title_eng: String::new(), start_date: String::new(), 
-                          current_day: Utc::now().weekday().number_from_monday() as i32,
+                          title: String::new(), title_eng: String::new(),
+                          title_jp: String::new(), synonyms: Vec::new(),
+                          start_date: Arc::from(""), first_aired: None,
+                          airing_time: None, broadcast: None,
+                          genres: Vec::new(), studios: Vec::new(), source: None, mean_score: None,
+                          episode_seconds: None,
+                          current_day: Local::now().weekday().number_from_monday() as i32,
                           anime_airing_day: 0}
     }
 }
@@ -84,67 +358,42 @@ impl AnimeAttributes {
         Default::default()
     }
 
-    pub fn register_attrib(&mut self, user: &UserAttributes, keyword: &str, 
-                           value: &str, value_rec: &str) -> Result<usize, Box<dyn std::error::Error>> {
-        let mut i_forward = 1;
-
-//        println!("keyword: {} value: {}", keyword, value);
-
-        match keyword {
-            "status" => {
-                self.status = parse_i32(value)?;
-            },
-            "score" => {
-                self.score = parse_i32(value)?;
-            },
-            "anime_id" => {
-                self.id = parse_i32(value)?; 
-            },
-            "num_watched_episodes" => {
-                self.id = parse_i32(value)?;
-            },
-            "anime_num_episodes" => {
-                self.num_episodes = parse_i32(value)?;
+    /// set the start date as shown on the list page, in the user's date
+    /// format, along with the weekday the anime airs on.
+    pub fn set_start_date(&mut self, user: &UserAttributes, value: &str) -> Result<(), CrawlerError> {
+        self.start_date = intern::global().intern(value);
+        self.anime_airing_day = match NaiveDate::parse_from_str(&self.start_date, &user.date_format) {
+            Ok(date_parsed) => {
+                self.first_aired = Some(date_parsed);
+                date_parsed.weekday().number_from_monday() as i32
             },
-            "is_rewatching" => {
-                self.is_rewatching = parse_i32(value)? == 1;
-            },
-            "anime_airing_status" => {
-                self.is_airing = parse_i32(value)? == 1;
-            },
-            "anime_title" => {
-                self.title = String::from(value_rec);
-                i_forward += 1;
-            },
-            "anime_title_eng" => {
-                self.title_eng = String::from(value_rec);
-                i_forward += 1;
-            },
-            "anime_start_date_string" => {
-                self.start_date = String::from(value_rec);     
-                self.anime_airing_day = match NaiveDate::parse_from_str(&self.start_date, &user.date_format) {
-                    Ok(date_parsed) => {
-                        date_parsed.weekday().number_from_monday() as i32
-                    },
-                    Err(_) => {
-                        NaiveDate::parse_from_str(&self.start_date, &user.date_format_backup)?.weekday().num_days_from_monday() as i32
-                    }
-                };
-                
-                i_forward += 1;
-            },
-            _ => {
-            
+            Err(_) => {
+                let date_parsed = parse_date(&self.start_date, &user.date_format_backup)?;
+                self.first_aired = Some(date_parsed);
+                date_parsed.weekday().num_days_from_monday() as i32
             }
         };
-        Ok(i_forward)
+        Ok(())
     }
 
     /// returns True if there is a possibility that the anime might
     /// be airing today. This happens due to the time zone differences.
+    /// Finished anime never air again, so their page is not worth fetching.
     pub fn should_get_precise_day(&self) -> bool {
         let day_diff = self.current_day - self.anime_airing_day;
-        day_diff >= 0 && day_diff <= 1 
+        self.is_airing && (0..=1).contains(&day_diff)
+    }
+
+    /// set the weekday the anime airs on in the local time, e.g. from the
+    /// broadcast the MAL API reports.
+    pub fn set_airing_weekday(&mut self, weekday: Weekday) {
+        self.anime_airing_day = weekday.number_from_monday() as i32;
+    }
+
+    /// set the weekday it is today in the zone airing days are shifted to,
+    /// the local one by default.
+    pub fn set_today(&mut self, today: Weekday) {
+        self.current_day = today.number_from_monday() as i32;
     }
 
     /// update the airing date of the anime by using the datetime
@@ -161,5 +410,279 @@ impl AnimeAttributes {
     pub fn is_finished(&self) -> bool {
         !self.is_airing
     }
+
+    /// the romaji title, the English title, the Japanese title and the
+    /// synonyms of the entry, leaving out empty ones.
+    pub fn titles(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(&self.title).chain(std::iter::once(&self.title_eng))
+                                    .chain(std::iter::once(&self.title_jp))
+                                    .chain(self.synonyms.iter())
+                                    .map(|title| title.as_str())
+                                    .filter(|title| !title.is_empty())
+    }
+
+    /// title of the entry in the given language, the romaji title if the
+    /// entry has none in that language.
+    pub fn preferred_title(&self, language: TitleLanguage) -> &str {
+        let title = match language {
+            TitleLanguage::Romaji => &self.title,
+            TitleLanguage::English => &self.title_eng,
+            TitleLanguage::Japanese => &self.title_jp,
+        };
+        if title.is_empty() { &self.title } else { title }
+    }
+
+    /// the title in the language the user prefers. Entries without an
+    /// English or Japanese title show the romaji one.
+    pub fn display_title(&self, user: &UserAttributes) -> &str {
+        self.preferred_title(user.title_language)
+    }
+
+    /// weekday the anime airs on in the local time, None if it is not airing.
+    pub fn airing_weekday(&self) -> Option<Weekday> {
+        if !self.is_airing {
+            return None;
+        }
+        // days are numbered from monday = 1, shifting may step over the week.
+        Weekday::try_from((self.anime_airing_day - 1).rem_euclid(7) as u8).ok()
+    }
+
+    /// local date and time the next episode airs at after `now`, if the
+    /// airing time is known.
+    pub fn next_airing(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let weekday = self.airing_weekday()?;
+        let time = self.airing_time?;
+        let days_ahead = (weekday.num_days_from_monday() as i64
+                          - now.weekday().num_days_from_monday() as i64).rem_euclid(7);
+        let next = (now.date() + chrono::Duration::days(days_ahead)).and_time(time);
+        if next <= now {
+            Some(next + chrono::Duration::days(7))
+        } else {
+            Some(next)
+        }
+    }
+
+    /// local date the next episode airs on, today if it airs today and the
+    /// airing time is not known.
+    fn next_airing_date(&self, now: NaiveDateTime) -> Option<NaiveDate> {
+        if let Some(next) = self.next_airing(now) {
+            return Some(next.date());
+        }
+        let weekday = self.airing_weekday()?;
+        let days_ahead = (weekday.num_days_from_monday() as i64
+                          - now.weekday().num_days_from_monday() as i64).rem_euclid(7);
+        Some(now.date() + chrono::Duration::days(days_ahead))
+    }
+
+    /// number of the next episode to air after `now`, counting an episode a
+    /// week since the start date. Breaks in the broadcast are not known, so
+    /// the number runs ahead after them.
+    pub fn next_episode_number(&self, now: NaiveDateTime) -> Option<i32> {
+        let first_aired = self.first_aired?;
+        let next = self.next_airing_date(now)?;
+        // the local date is a day off the start date in JST for late night
+        // and early morning broadcasts.
+        let weeks = ((next - first_aired).num_days() as f64 / 7.0).round() as i32;
+        Some(weeks.max(0) + 1)
+    }
+
+    /// episodes still to air after `now`, None if MAL does not know how
+    /// many episodes the anime has.
+    pub fn episodes_remaining(&self, now: NaiveDateTime) -> Option<i32> {
+        if self.num_episodes == 0 {
+            return None;
+        }
+        if !self.is_airing {
+            return Some(0);
+        }
+        let next = self.next_episode_number(now)?;
+        Some((self.num_episodes - next + 1).max(0))
+    }
+
+    /// local date and time the next episode airs at after `now`, None if
+    /// the airing time is not known or every episode has aired.
+    pub fn next_episode_at(&self, now: NaiveDateTime) -> Option<DateTime<Local>> {
+        if self.episodes_remaining(now) == Some(0) {
+            return None;
+        }
+        self.next_airing(now)?.and_local_timezone(Local).earliest()
+    }
+
+    /// time left until the next broadcast, e.g. to show "airs in 3 hours",
+    /// None if the broadcast slot is not known.
+    pub fn time_until_airing(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        if !self.is_airing {
+            return None;
+        }
+        self.broadcast.as_ref()?.next_airing(now).map(|next| next - now)
+    }
+}
+
+
+#[test]
+fn test_should_get_precise_day() {
+    let mut anime = AnimeAttributes::new();
+    anime.anime_airing_day = anime.current_day;
+    assert!(!anime.should_get_precise_day());
+
+    anime.is_airing = true;
+    assert!(anime.should_get_precise_day());
+
+    anime.anime_airing_day = anime.current_day - 3;
+    assert!(!anime.should_get_precise_day());
+
+    // today is the weekday of the zone the airing day is shifted to.
+    anime.set_airing_weekday(Weekday::Fri);
+    anime.set_today(Weekday::Sat);
+    assert!(!anime.is_airing_today() && anime.should_get_precise_day());
+    anime.set_today(Weekday::Fri);
+    assert!(anime.is_airing_today());
+}
+
+#[test]
+fn test_next_airing() {
+    let mut anime = AnimeAttributes::new();
+    anime.is_airing = true;
+    anime.update_airing_day(5);
+    assert_eq!(anime.next_airing(NaiveDate::from_ymd_opt(2023, 10, 2).unwrap().and_hms_opt(0, 0, 0).unwrap()), None);
+
+    anime.airing_time = NaiveTime::from_hms_opt(17, 0, 0);
+    // a monday, so the next friday of the same week.
+    let monday = NaiveDate::from_ymd_opt(2023, 10, 2).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    let friday = NaiveDate::from_ymd_opt(2023, 10, 6).unwrap().and_hms_opt(17, 0, 0).unwrap();
+    assert_eq!(anime.next_airing(monday), Some(friday));
+    // right after the episode aired, the next one is a week later.
+    let aired = friday + chrono::Duration::minutes(1);
+    assert_eq!(anime.next_airing(aired), Some(friday + chrono::Duration::days(7)));
+}
+
+#[test]
+fn test_next_episode() {
+    let mut anime = AnimeAttributes::new();
+    anime.is_airing = true;
+    anime.num_episodes = 28;
+    anime.set_airing_weekday(Weekday::Fri);
+    // the first episode aired on friday 2023-09-29.
+    anime.first_aired = NaiveDate::from_ymd_opt(2023, 9, 29);
+    let monday = NaiveDate::from_ymd_opt(2023, 11, 6).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    assert_eq!(anime.next_episode_number(monday), Some(7));
+    assert_eq!(anime.episodes_remaining(monday), Some(22));
+    // without the airing time the day is known, not the hour.
+    assert_eq!(anime.next_episode_at(monday), None);
+
+    anime.airing_time = NaiveTime::from_hms_opt(17, 0, 0);
+    let friday = NaiveDate::from_ymd_opt(2023, 11, 10).unwrap().and_hms_opt(17, 0, 0).unwrap();
+    assert_eq!(anime.next_episode_at(monday).map(|next| next.naive_local()), Some(friday));
+    // episodes airing past midnight fall on the next local day.
+    anime.set_airing_weekday(Weekday::Sat);
+    anime.airing_time = NaiveTime::from_hms_opt(1, 0, 0);
+    assert_eq!(anime.next_episode_number(monday), Some(7));
+
+    anime.num_episodes = 6;
+    assert_eq!(anime.episodes_remaining(monday), Some(0));
+    assert_eq!(anime.next_episode_at(monday), None);
+    anime.num_episodes = 0;
+    assert_eq!(anime.episodes_remaining(monday), None);
 }
 
+#[test]
+fn test_serde() {
+    let item = serde_json::json!({"status": 1, "score": 9, "is_rewatching": 0, "num_watched_episodes": 7,
+                                  "anime_id": 52991, "anime_title": "Sousou no Frieren", "anime_title_eng": null,
+                                  "anime_num_episodes": 28, "anime_airing_status": 1,
+                                  "anime_start_date_string": "29-09-2023", "tags": "",
+                                  "genres": [{"id": 2, "name": "Adventure"}, {"id": 8, "name": "Drama"}]});
+    let mut anime: AnimeAttributes = serde_json::from_value(item).unwrap();
+    assert_eq!((anime.id, anime.num_watched_episodes, anime.num_episodes), (52991, 7, 28));
+    assert_eq!((anime.title.as_str(), anime.title_eng.as_str()), ("Sousou no Frieren", ""));
+    assert!(anime.is_airing && !anime.is_rewatching);
+    assert_eq!(&*anime.start_date, "29-09-2023");
+    assert_eq!(anime.genres, ["Adventure", "Drama"]);
+    assert_eq!(anime.current_day, AnimeAttributes::new().current_day);
+    // the airing day comes from the start date, in the user's format.
+    let mut user = UserAttributes::new(String::from("goksgie"));
+    user.set_date_format(String::from("1")).unwrap();
+    let start_date = anime.start_date.clone();
+    anime.set_start_date(&user, &start_date).unwrap();
+    anime.airing_time = NaiveTime::from_hms_opt(23, 0, 0);
+    anime.broadcast = BroadcastSchedule::parse("Fridays at 23:00 (JST)");
+
+    let json = serde_json::to_value(&anime).unwrap();
+    assert_eq!(json["anime_airing_status"], 1);
+    assert_eq!(json["anime_title"], "Sousou no Frieren");
+    let parsed: AnimeAttributes = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed.airing_weekday(), Some(Weekday::Fri));
+    assert_eq!(parsed.first_aired, NaiveDate::from_ymd_opt(2023, 9, 29));
+    assert_eq!(parsed.broadcast, anime.broadcast);
+
+    let user: UserAttributes = serde_json::from_str("{\"uname\": \"goksgie\", \"list_status\": \"on_hold\"}").unwrap();
+    assert_eq!((user.uname.as_str(), user.list_status), ("goksgie", ListStatus::OnHold));
+    assert!(!user.has_date_format());
+}
+
+#[test]
+fn test_entry_keys() {
+    // every field is read under one of the keys, and every key is read.
+    let json = serde_json::to_value(AnimeAttributes::new()).unwrap();
+    let mut fields: Vec<&str> = json.as_object().unwrap().keys().map(String::as_str).collect();
+    fields.push("anime_studios");
+    fields.sort_unstable();
+    let mut keys = ENTRY_KEYS.to_vec();
+    keys.sort_unstable();
+    assert_eq!(fields, keys);
+}
+
+#[test]
+fn test_set_list_status() {
+    let mut user = UserAttributes::new(String::from("test"));
+    assert_eq!(user.list_status, ListStatus::Watching);
+    user.set_list_status("6").unwrap();
+    assert_eq!(user.list_status, ListStatus::PlanToWatch);
+    assert!(user.set_list_status("5").is_err());
+    assert_eq!(ListStatus::from_api_name("on_hold").map(ListStatus::number), Some(3));
+    assert_eq!(ListStatus::All.api_name(), None);
+}
+
+#[test]
+fn test_display_title() {
+    let mut anime = AnimeAttributes::new();
+    anime.title = String::from("Sousou no Frieren");
+    anime.title_eng = String::from("Frieren: Beyond Journey's End");
+    let mut user = UserAttributes::new(String::from("goksgie"));
+    assert_eq!(anime.display_title(&user), "Sousou no Frieren");
+    user.title_language = TitleLanguage::English;
+    assert_eq!(anime.display_title(&user), "Frieren: Beyond Journey's End");
+    // no Japanese title was read, the romaji one stands in.
+    user.title_language = TitleLanguage::Japanese;
+    assert_eq!(anime.display_title(&user), "Sousou no Frieren");
+
+    let user: UserAttributes = serde_json::from_str("{\"uname\": \"goksgie\", \"title_language\": \"english\"}").unwrap();
+    assert_eq!(user.title_language, TitleLanguage::English);
+}
+
+#[test]
+fn test_infer_date_format() {
+    assert_eq!(infer_date_format(vec!["01-02-2023", "29-09-2023"]), Some("1"));
+    assert_eq!(infer_date_format(vec!["09-29-2023", "01-02-2023"]), Some("2"));
+    assert_eq!(infer_date_format(vec!["01-02-2023", "??-??-2024", ""]), None);
+}
+
+#[test]
+fn test_broadcast_schedule() {
+    let schedule = BroadcastSchedule::parse("Saturdays at 23:00 (JST)").unwrap();
+    assert_eq!(schedule, BroadcastSchedule::jst(Weekday::Sat, NaiveTime::from_hms_opt(23, 0, 0).unwrap()));
+    assert_eq!(schedule.to_string(), "Saturdays at 23:00 (JST)");
+    assert_eq!(BroadcastSchedule::parse("Unknown"), None);
+    assert_eq!(BroadcastSchedule::parse("Saturdays at Unknown"), None);
+
+    // 13:00 UTC on a saturday is 22:00 JST, an hour before the broadcast.
+    let now = Utc.with_ymd_and_hms(2023, 10, 7, 13, 0, 0).unwrap();
+    assert_eq!(schedule.next_airing(now), Some(Utc.with_ymd_and_hms(2023, 10, 7, 14, 0, 0).unwrap()));
+    let mut anime = AnimeAttributes::new();
+    anime.broadcast = Some(schedule);
+    assert_eq!(anime.time_until_airing(now), None);
+    anime.is_airing = true;
+    assert_eq!(anime.time_until_airing(now), Some(chrono::Duration::hours(1)));
+    // right after the broadcast, the next one is a week later.
+    assert_eq!(anime.time_until_airing(now + chrono::Duration::hours(1)), Some(chrono::Duration::days(7)));
+}
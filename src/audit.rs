@@ -0,0 +1,158 @@
+// Audit log of the traffic the crawler generates, so users can verify how
+// many requests a run sent and how much it downloaded.
+//
+// Every request sent over the network and every anime page answered from
+// the broadcast cache is appended to the log file as a
+// "timestamp status bytes hit|miss url" line, timestamp in seconds since the
+// epoch and status "-" for requests that failed without a response. Replayed
+// fixtures and dry runs send nothing and are not logged.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::warn;
+
+use crate::config;
+use crate::error::CrawlerError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    /// seconds since the epoch.
+    pub timestamp: u64,
+    pub url: String,
+    /// HTTP status of the response, None if there was none.
+    pub status: Option<u16>,
+    /// size of the response body.
+    pub bytes: usize,
+    /// true if answered from the cache without a request.
+    pub cache_hit: bool,
+}
+
+impl AuditEntry {
+    fn to_line(&self) -> String {
+        let status = self.status.map_or(String::from("-"), |status| status.to_string());
+        let cache = if self.cache_hit { "hit" } else { "miss" };
+        format!("{} {} {} {} {}", self.timestamp, status, self.bytes, cache, self.url)
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(5, ' ');
+        let timestamp = fields.next()?.parse::<u64>().ok()?;
+        let status = match fields.next()? {
+            "-" => None,
+            status => Some(status.parse::<u16>().ok()?),
+        };
+        let bytes = fields.next()?.parse::<usize>().ok()?;
+        let cache_hit = match fields.next()? {
+            "hit" => true,
+            "miss" => false,
+            _ => return None,
+        };
+        let url = String::from(fields.next()?);
+        Some(AuditEntry { timestamp, url, status, bytes, cache_hit })
+    }
+}
+
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+/// default location of the log, in the data directory.
+pub fn default_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("audit-log.txt"))
+}
+
+impl AuditLog {
+    /// open the log at the given path, appending to earlier runs.
+    pub fn open(path: PathBuf) -> Result<Self, CrawlerError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| {
+                CrawlerError::io(format!("could not create {}", dir.display()), err)
+            })?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path).map_err(|err| {
+            CrawlerError::io(format!("could not open audit log {}", path.display()), err)
+        })?;
+        Ok(AuditLog { path, file: Mutex::new(file) })
+    }
+
+    /// open the log in the data directory.
+    pub fn open_default() -> Result<Self, CrawlerError> {
+        let path = default_path().ok_or_else(|| {
+            CrawlerError::io("could not locate the data directory",
+                             std::io::Error::from(std::io::ErrorKind::NotFound))
+        })?;
+        AuditLog::open(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&self, entry: &AuditEntry) -> Result<(), CrawlerError> {
+        let mut file = match self.file.lock() {
+            Ok(file) => file,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        writeln!(file, "{}", entry.to_line()).and_then(|_| file.flush()).map_err(|err| {
+            CrawlerError::io(format!("could not write audit log {}", self.path.display()), err)
+        })
+    }
+}
+
+/// every entry of the log at the given path that was recorded at or after
+/// `since` (seconds since the epoch). Broken lines are skipped.
+pub fn read(path: &Path, since: u64) -> Result<Vec<AuditEntry>, CrawlerError> {
+    let contents = fs::read_to_string(path).map_err(|err| {
+        CrawlerError::io(format!("could not read audit log {}", path.display()), err)
+    })?;
+    Ok(contents.lines()
+               .filter_map(AuditEntry::from_line)
+               .filter(|entry| entry.timestamp >= since)
+               .collect())
+}
+
+static LOG: OnceLock<AuditLog> = OnceLock::new();
+
+/// Log the requests of the whole process to the given log. Only the first
+/// call takes effect, later ones get their log back.
+pub fn install(log: AuditLog) -> Result<(), AuditLog> {
+    LOG.set(log)
+}
+
+/// append an entry to the installed log, if there is one.
+pub(crate) fn record(url: &str, status: Option<u16>, bytes: usize, cache_hit: bool) {
+    if let Some(log) = LOG.get() {
+        let entry = AuditEntry { timestamp: now_secs(), url: String::from(url), status, bytes, cache_hit };
+        if let Err(err) = log.record(&entry) {
+            warn!(%err, "could not update the audit log");
+        }
+    }
+}
+
+
+#[test]
+fn test_audit_log() {
+    let path = std::env::temp_dir().join(format!("malcrawler-audit-{}.txt", std::process::id()));
+    let log = AuditLog::open(path.clone()).unwrap();
+    let fetched = AuditEntry { timestamp: 1_700_000_000, url: String::from("https://myanimelist.net/anime/52991/"),
+                               status: Some(200), bytes: 512, cache_hit: false };
+    let failed = AuditEntry { timestamp: 1_700_000_100, url: String::from("https://myanimelist.net/robots.txt"),
+                              status: None, bytes: 0, cache_hit: false };
+    log.record(&fetched).unwrap();
+    log.record(&failed).unwrap();
+    drop(log);
+
+    assert_eq!(read(&path, 0).unwrap(), vec![fetched, failed.clone()]);
+    assert_eq!(read(&path, 1_700_000_050).unwrap(), vec![failed]);
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,163 @@
+// Broadcast times of anime pages persisted between runs. Broadcast slots
+// rarely change within a season, so repeat runs can skip nearly every anime
+// page request.
+//
+// Every fetched page is appended to the cache file as an
+// "anime_id fetched_at HH:MM title" line, fetched_at in seconds since the
+// epoch, the broadcast time in JST, or "-" for pages without one, and the
+// Japanese title of the anime if it is known. Opening the cache drops expired
+// lines from the file.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::NaiveTime;
+
+use crate::config;
+use crate::error::CrawlerError;
+
+#[derive(Debug)]
+pub struct BroadcastCache {
+    path: PathBuf,
+    ttl: Duration,
+    times: HashMap<i32, CachedPage>,
+    file: Mutex<File>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+#[derive(Debug)]
+struct CachedPage {
+    fetched_at: u64,
+    time: Option<NaiveTime>,
+    title_jp: Option<String>,
+}
+
+fn format_line(anime_id: i32, page: &CachedPage) -> String {
+    let time = page.time.map_or(String::from("-"), |time| time.format("%H:%M").to_string());
+    match &page.title_jp {
+        Some(title_jp) => format!("{} {} {} {}", anime_id, page.fetched_at, time, title_jp),
+        None => format!("{} {} {}", anime_id, page.fetched_at, time),
+    }
+}
+
+impl BroadcastCache {
+    /// open the cache at the given path, entries older than `ttl` are dropped.
+    pub fn open(path: PathBuf, ttl: Duration) -> Result<Self, CrawlerError> {
+        let now = now_secs();
+        let mut times = HashMap::new();
+        if let Ok(contents) = fs::read_to_string(&path) {
+            // later lines win, broken lines are ignored.
+            for line in contents.lines() {
+                let mut fields = line.splitn(4, ' ');
+                let anime_id = fields.next().and_then(|id| id.parse::<i32>().ok());
+                let fetched_at = fields.next().and_then(|secs| secs.parse::<u64>().ok());
+                let time = match fields.next() {
+                    Some("-") => Some(None),
+                    Some(time) => NaiveTime::parse_from_str(time, "%H:%M").ok().map(Some),
+                    None => None,
+                };
+                let title_jp = fields.next().filter(|title| !title.is_empty()).map(String::from);
+                if let (Some(anime_id), Some(fetched_at), Some(time)) = (anime_id, fetched_at, time) {
+                    if now.saturating_sub(fetched_at) < ttl.as_secs() {
+                        times.insert(anime_id, CachedPage { fetched_at, time, title_jp });
+                    }
+                }
+            }
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| {
+                CrawlerError::io(format!("could not create {}", dir.display()), err)
+            })?;
+        }
+        let compacted: String = times.iter()
+                                     .map(|(anime_id, page)| format_line(*anime_id, page) + "\n")
+                                     .collect();
+        fs::write(&path, compacted).map_err(|err| {
+            CrawlerError::io(format!("could not write broadcast cache {}", path.display()), err)
+        })?;
+        let file = OpenOptions::new().append(true).open(&path).map_err(|err| {
+            CrawlerError::io(format!("could not open broadcast cache {}", path.display()), err)
+        })?;
+
+        Ok(BroadcastCache { path, ttl, times, file: Mutex::new(file) })
+    }
+
+    /// open the cache in the data directory.
+    pub fn open_default(ttl: Duration) -> Result<Self, CrawlerError> {
+        let dir = config::data_dir().ok_or_else(|| {
+            CrawlerError::io("could not locate the data directory",
+                             std::io::Error::from(std::io::ErrorKind::NotFound))
+        })?;
+        BroadcastCache::open(dir.join("broadcast-cache.txt"), ttl)
+    }
+
+    /// broadcast time of the anime in JST if it has been cached, Some(None)
+    /// if its page had none.
+    pub fn get(&self, anime_id: i32) -> Option<Option<NaiveTime>> {
+        self.times.get(&anime_id).map(|page| page.time)
+    }
+
+    /// Japanese title of the anime, if its page had one when cached.
+    pub fn japanese_title(&self, anime_id: i32) -> Option<&str> {
+        self.times.get(&anime_id).and_then(|page| page.title_jp.as_deref())
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// number of anime loaded from the cache file.
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    pub fn record(&self, anime_id: i32, time: Option<NaiveTime>, title_jp: Option<&str>) -> Result<(), CrawlerError> {
+        let mut file = match self.file.lock() {
+            Ok(file) => file,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let page = CachedPage { fetched_at: now_secs(), time, title_jp: title_jp.map(String::from) };
+        writeln!(file, "{}", format_line(anime_id, &page)).and_then(|_| file.flush()).map_err(|err| {
+            CrawlerError::io(format!("could not write broadcast cache {}", self.path.display()), err)
+        })
+    }
+}
+
+
+#[test]
+fn test_broadcast_cache() {
+    let path = std::env::temp_dir().join(format!("malcrawler-broadcast-{}.txt", std::process::id()));
+    let day = Duration::from_secs(24 * 60 * 60);
+    let cache = BroadcastCache::open(path.clone(), day).unwrap();
+    assert!(cache.is_empty());
+    let time = NaiveTime::from_hms_opt(23, 0, 0);
+    cache.record(52991, time, Some("葬送のフリーレン")).unwrap();
+    cache.record(457, None, None).unwrap();
+    drop(cache);
+
+    let cache = BroadcastCache::open(path.clone(), day).unwrap();
+    assert_eq!(cache.get(52991), Some(time));
+    assert_eq!(cache.get(457), Some(None));
+    assert_eq!(cache.japanese_title(52991), Some("葬送のフリーレン"));
+    assert_eq!(cache.japanese_title(457), None);
+    assert_eq!(cache.get(1), None);
+    drop(cache);
+
+    // everything expires with a zero ttl, and is dropped from the file.
+    let expired = BroadcastCache::open(path.clone(), Duration::from_secs(0)).unwrap();
+    assert!(expired.is_empty());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "");
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,165 @@
+// Progress of an in-progress crawl, so that an interrupted crawl of a huge
+// list resumes where it left off instead of fetching every anime page again.
+//
+// Every fetched anime page is appended to the checkpoint file as an
+// "anime_id shifting HH:MM title" line right away, the broadcast time in JST
+// "-" for pages without one, and the Japanese title of the anime left out if
+// it is not known. The day shifts only hold in the zone they were computed
+// for, so the file starts with a "minutes_to_jst N" line and a crawl in
+// another zone discards it. The file is removed once a crawl completes.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use chrono::NaiveTime;
+
+use crate::config;
+use crate::error::CrawlerError;
+
+const ZONE_KEY: &str = "minutes_to_jst";
+
+#[derive(Debug)]
+pub struct Checkpoint {
+    path: PathBuf,
+    fetched: HashMap<i32, (i32, Option<NaiveTime>)>,
+    titles_jp: HashMap<i32, String>,
+    file: Mutex<File>,
+}
+
+impl Checkpoint {
+    /// open the checkpoint at the given path, loading the progress of a
+    /// previous, interrupted crawl if there is one and its day shifts were
+    /// computed `minutes_to_jst` minutes behind JST as well.
+    pub fn open(path: PathBuf, minutes_to_jst: i32) -> Result<Self, CrawlerError> {
+        let mut fetched = HashMap::new();
+        let mut titles_jp = HashMap::new();
+        let header = format!("{} {}", ZONE_KEY, minutes_to_jst);
+        let contents = fs::read_to_string(&path).unwrap_or_default();
+        let mut lines = contents.lines();
+        let resumed = lines.next() == Some(header.as_str());
+        if !resumed && !contents.is_empty() {
+            tracing::info!(path = %path.display(), "discarding the checkpoint of a crawl in another zone");
+        }
+        if resumed {
+            // a line cut short by the interruption is simply ignored.
+            for line in lines {
+                let mut fields = line.splitn(4, ' ');
+                let anime_id = fields.next().and_then(|id| id.parse::<i32>().ok());
+                let shifting = fields.next().and_then(|shift| shift.parse::<i32>().ok());
+                let time = fields.next().and_then(|time| NaiveTime::parse_from_str(time, "%H:%M").ok());
+                let title_jp = fields.next().filter(|title| !title.is_empty());
+                if let (Some(anime_id), Some(shifting)) = (anime_id, shifting) {
+                    fetched.insert(anime_id, (shifting, time));
+                    if let Some(title_jp) = title_jp {
+                        titles_jp.insert(anime_id, String::from(title_jp));
+                    }
+                }
+            }
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| {
+                CrawlerError::io(format!("could not create {}", dir.display()), err)
+            })?;
+        }
+        let open_error = |err| CrawlerError::io(format!("could not open checkpoint {}", path.display()), err);
+        let file = if resumed {
+            OpenOptions::new().append(true).open(&path).map_err(open_error)?
+        } else {
+            let mut file = File::create(&path).map_err(open_error)?;
+            writeln!(file, "{}", header).map_err(open_error)?;
+            file
+        };
+
+        Ok(Checkpoint { path, fetched, titles_jp, file: Mutex::new(file) })
+    }
+
+    /// open the checkpoint of the given user in the data directory.
+    pub fn for_user(uname: &str, minutes_to_jst: i32) -> Result<Self, CrawlerError> {
+        let dir = config::data_dir().ok_or_else(|| {
+            CrawlerError::io("could not locate the data directory",
+                             std::io::Error::from(std::io::ErrorKind::NotFound))
+        })?;
+        let uname: String = uname.chars()
+                                 .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+                                 .collect();
+        Checkpoint::open(dir.join(format!("checkpoint-{}.txt", uname)), minutes_to_jst)
+    }
+
+    /// day shift and broadcast time of an anime page fetched by the
+    /// interrupted crawl.
+    pub fn get(&self, anime_id: i32) -> Option<(i32, Option<NaiveTime>)> {
+        self.fetched.get(&anime_id).copied()
+    }
+
+    /// Japanese title of an anime page fetched by the interrupted crawl.
+    pub fn japanese_title(&self, anime_id: i32) -> Option<&str> {
+        self.titles_jp.get(&anime_id).map(String::as_str)
+    }
+
+    /// number of anime pages restored from the interrupted crawl.
+    pub fn len(&self) -> usize {
+        self.fetched.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fetched.is_empty()
+    }
+
+    pub fn record(&self, anime_id: i32, shifting: i32, time: Option<NaiveTime>,
+                  title_jp: Option<&str>) -> Result<(), CrawlerError> {
+        let mut file = match self.file.lock() {
+            Ok(file) => file,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let time = time.map_or(String::from("-"), |time| time.format("%H:%M").to_string());
+        let line = match title_jp {
+            Some(title_jp) => format!("{} {} {} {}", anime_id, shifting, time, title_jp),
+            None => format!("{} {} {}", anime_id, shifting, time),
+        };
+        writeln!(file, "{}", line).and_then(|_| file.flush()).map_err(|err| {
+            CrawlerError::io(format!("could not write checkpoint {}", self.path.display()), err)
+        })
+    }
+
+    /// remove the checkpoint after the crawl completed.
+    pub fn finish(self) -> Result<(), CrawlerError> {
+        fs::remove_file(&self.path).map_err(|err| {
+            CrawlerError::io(format!("could not remove checkpoint {}", self.path.display()), err)
+        })
+    }
+}
+
+
+#[test]
+fn test_resume_from_checkpoint() {
+    let path = std::env::temp_dir().join(format!("malcrawler-checkpoint-{}.txt", std::process::id()));
+    let checkpoint = Checkpoint::open(path.clone(), 0).unwrap();
+    assert!(checkpoint.is_empty());
+    let time = NaiveTime::from_hms_opt(1, 30, 0);
+    checkpoint.record(5081, -1, time, Some("化物語")).unwrap();
+    checkpoint.record(457, 0, None, None).unwrap();
+    drop(checkpoint);
+
+    let resumed = Checkpoint::open(path.clone(), 0).unwrap();
+    assert_eq!(resumed.get(5081), Some((-1, time)));
+    assert_eq!(resumed.get(457), Some((0, None)));
+    assert_eq!(resumed.japanese_title(5081), Some("化物語"));
+    assert_eq!(resumed.japanese_title(457), None);
+    assert_eq!(resumed.get(1), None);
+    drop(resumed);
+
+    // the shifts of a crawl in another zone do not apply.
+    let moved = Checkpoint::open(path.clone(), 9 * 60).unwrap();
+    assert!(moved.is_empty());
+    moved.record(457, 1, None, None).unwrap();
+    drop(moved);
+    let resumed = Checkpoint::open(path.clone(), 9 * 60).unwrap();
+    assert_eq!(resumed.get(457), Some((1, None)));
+    assert_eq!(resumed.get(5081), None);
+    resumed.finish().unwrap();
+    assert!(!path.exists());
+}
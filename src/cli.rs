@@ -0,0 +1,510 @@
+// Command line flags of the interactive binary.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::FixedOffset;
+
+use anime_crawler::anime::ListStatus;
+use anime_crawler::requester::Season;
+use anime_crawler::config::{self, ProxyConfig};
+use anime_crawler::query::{Filter, Query, SortKey};
+use anime_crawler::rate_limit::RateLimiter;
+use anime_crawler::http::HttpConfig;
+use anime_crawler::notify::Notifier;
+use anime_crawler::titles::TitleLanguage;
+use anime_crawler::transport::Transport;
+
+use crate::logging::Verbosity;
+
+pub const USAGE: &str = "\
+Usage: anime-crawler [OPTIONS]
+       anime-crawler --user <NAME> [--date-format <FORMAT>] [--status <STATUS>] [OPTIONS]
+       anime-crawler --users <NAME,NAME,..> [--date-format <FORMAT>] [--status <STATUS>] [OPTIONS]
+       anime-crawler export [--svg <FILE>] [--ics <FILE>] --user <NAME> [--date-format <FORMAT>] [OPTIONS]
+       anime-crawler stats --user <NAME> [--date-format <FORMAT>] [OPTIONS]
+       anime-crawler log [--since <HOURS>]
+       anime-crawler season [--year <YEAR>] [--season <SEASON>] [--today]
+
+Without --user, --users or a command, user name, date format and list are asked for
+interactively, one list after the other.
+Defaults of the options are read from config.toml in the data directory.
+
+Commands:
+    export             crawl the list of a user once and render the weekly
+                       schedule to an SVG image or an iCalendar file
+    stats              crawl the whole list of a user and print the entries per
+                       section, the scores given and the time spent watching
+    log                print the requests recorded in the audit log
+    season             list every anime starting in a season
+
+List options:
+    --user <NAME>      user whose list is crawled
+    --output <FILE>    also write the crawled entries to FILE, as CSV if it ends
+                       in .csv and as JSON otherwise
+    --users <NAME,NAME,..>
+                       crawl the lists of several users at once, each printed
+                       on its own
+    --date-format <FORMAT>
+                       dmy or 1 for day-month-year, mdy or 2 for month-day-year
+                       (default: detected from the list)
+    --status <STATUS>  section of the list: watching (default), completed, on_hold,
+                       dropped, plan_to_watch or all
+    --only-airing-today
+                       only entries airing today
+    --min-score <SCORE>
+                       only entries with a community score of at least SCORE
+    --genre <GENRE>    only entries of the genre, e.g. Fantasy
+    --search <TEXT>    only entries with a title loosely matching TEXT, e.g. sxf
+                       for Spy x Family, best match first
+    --sort <KEY>       order the entries by airing-time, title or score
+    --notify <TARGET>  post the entries airing today to discord after every crawl,
+                       to the discord_webhook of config.toml
+
+Export options:
+    --svg <FILE>       where to draw the schedule
+    --ics <FILE>       where to write the schedule as weekly recurring events
+
+Log options:
+    --since <HOURS>    only requests of the last HOURS hours
+
+Season options:
+    --year <YEAR>      year of the season (default: this year)
+    --season <SEASON>  winter, spring, summer or fall (default: the current season)
+    --today            only anime airing today
+
+Options:
+    --record           store every fetched page as a fixture
+    --offline          answer every request from recorded fixtures
+    --fixtures <DIR>   fixture directory (default: <data dir>/fixtures)
+    --dry-run          print every request instead of sending it
+    --utc-offset <+HH:MM>
+                       offset of the local time from UTC, e.g. +05:30 (default:
+                       detected from the host's timezone)
+    --requests-per-second <N>
+                       send at most N requests per second to MAL, shared by all
+                       workers (default: the crawl delay of robots.txt)
+    --metadata         fetch the genres, studios, source and score of every entry
+    --title <LANG>     show titles in romaji (default), english or japanese
+    --theme <NAME>     colors of the output: default, colorblind or plain
+    --tui              show the list as a weekly calendar, navigated with the arrow
+                       keys, r to crawl again, q to quit
+    --live             keep countdowns to the next episodes on screen, updated every minute
+    --watch <MINUTES>  crawl the list again every MINUTES minutes and notify when an
+                       entry airs today or its episode count changes
+    --scrape           scrape the list page even if MAL API credentials are set
+    --ignore-robots    do not honor robots.txt of myanimelist.net
+    --no-history       do not compare the list with the last crawl of it
+    --no-cache         fetch every anime page instead of using cached broadcast hours
+    --cache-days <DAYS>
+                       keep cached broadcast hours for DAYS days (default: 30)
+    --max-per-host <N> send at most N requests to a host at once (default: 4)
+    --workers <N>      fetch anime pages with N workers (default: 4)
+    -v, --verbose      also log the debug events of the crawl, with their timings
+    -q, --quiet        only log errors
+    --log-json         log every event as a line of JSON
+    --http1            do not negotiate HTTP/2
+    --proxy <URL>      send requests through the HTTP proxy at URL, e.g.
+                       http://proxy:3128 (default: the proxy of config.toml, then
+                       those of HTTP_PROXY, HTTPS_PROXY and ALL_PROXY)
+    --no-proxy         send requests directly, ignoring any configured proxy
+    --timeout <SECS>   give up on a request after SECS seconds, 0 waits forever
+                       (default: 30)
+    --keep-alive <SECS>
+                       keep idle connections open for SECS seconds, 0 closes them
+    -h, --help         print this help";
+
+#[derive(Debug)]
+pub enum Command {
+    /// crawl the lists of the users once, without prompting.
+    Crawl { users: Vec<String>, date_format: Option<String>, list_status: ListStatus },
+    /// render the weekly schedule of the user to an SVG image, an iCalendar file or both.
+    Export { svg: Option<PathBuf>, ics: Option<PathBuf>, user: String, date_format: Option<String>,
+             list_status: ListStatus },
+    /// crawl every section of the user's list and print its statistics.
+    Stats { user: String, date_format: Option<String> },
+    /// print the audit log, optionally only the last hours of it.
+    Log { since_hours: Option<u64> },
+    /// list the anime of a season, optionally only those airing today.
+    Season { year: i32, season: Season, today: bool },
+    /// print the usage and exit.
+    Help,
+}
+
+#[derive(Debug, Default)]
+pub struct Args {
+    /// runs the interactive loop when None.
+    pub command: Option<Command>,
+    pub transport: Transport,
+    pub ignore_robots: bool,
+    pub http: HttpConfig,
+    pub no_cache: bool,
+    /// do not report nor record the changes since the last crawl.
+    pub no_history: bool,
+    /// how long cached broadcast hours stay valid, the cache_days of config.toml if unset.
+    pub cache_ttl: Option<Duration>,
+    pub live: bool,
+    /// show the list in the terminal dashboard.
+    pub tui: bool,
+    /// file the crawled entries are written to.
+    pub output: Option<PathBuf>,
+    /// crawl the list again at this interval and notify about changes.
+    pub watch: Option<Duration>,
+    pub max_per_host: Option<usize>,
+    /// number of workers fetching anime pages, the workers of config.toml if unset.
+    pub workers: Option<usize>,
+    pub title_language: TitleLanguage,
+    pub theme: Option<String>,
+    pub scrape: bool,
+    /// fetch the genres, studios, source and score of every entry.
+    pub metadata: bool,
+    pub utc_offset: Option<FixedOffset>,
+    /// requests per second the whole crawl may send.
+    pub requests_per_second: Option<f64>,
+    /// filters and order of the crawled entries.
+    pub query: Query,
+    /// where the entries airing today are posted after a crawl.
+    pub notify: Option<Notifier>,
+    pub verbosity: Verbosity,
+    /// log events as JSON lines instead of text.
+    pub log_json: bool,
+}
+
+/// "dmy" and "mdy" as the numbers the date format prompt takes.
+fn parse_date_format(value: String) -> String {
+    match value.as_str() {
+        "dmy" => String::from("1"),
+        "mdy" => String::from("2"),
+        _ => value,
+    }
+}
+
+fn parse_list_status(value: &str) -> Result<ListStatus, String> {
+    match value {
+        "all" => Ok(ListStatus::All),
+        name => ListStatus::from_api_name(&name.replace('-', "_")).ok_or_else(|| {
+            format!("unknown list status {}, expected watching, completed, on_hold, dropped, \
+                     plan_to_watch or all", name)
+        }),
+    }
+}
+
+pub fn parse_args<I: Iterator<Item = String>>(mut raw_args: I) -> Result<Args, String> {
+    let mut record = false;
+    let mut offline = false;
+    let mut dry_run = false;
+    let mut ignore_robots = false;
+    let mut http = HttpConfig::default();
+    let mut no_cache = false;
+    let mut no_history = false;
+    let mut cache_ttl: Option<Duration> = None;
+    let mut live = false;
+    let mut tui = false;
+    let mut watch: Option<Duration> = None;
+    let mut max_per_host: Option<usize> = None;
+    let mut workers: Option<usize> = None;
+    let mut title_language = TitleLanguage::default();
+    let mut theme: Option<String> = None;
+    let mut scrape = false;
+    let mut metadata = false;
+    let mut utc_offset: Option<FixedOffset> = None;
+    let mut requests_per_second: Option<f64> = None;
+    let mut export = false;
+    let mut stats = false;
+    let mut log = false;
+    let mut since_hours: Option<u64> = None;
+    let mut season_command = false;
+    let mut year: Option<i32> = None;
+    let mut season: Option<Season> = None;
+    let mut today = false;
+    let mut command = None;
+    let mut svg: Option<PathBuf> = None;
+    let mut ics: Option<PathBuf> = None;
+    let mut output: Option<PathBuf> = None;
+    let mut user: Option<String> = None;
+    let mut users: Vec<String> = Vec::new();
+    let mut date_format: Option<String> = None;
+    let mut list_status: Option<ListStatus> = None;
+    let mut fixtures: Option<PathBuf> = None;
+    let mut query = Query::new();
+    let mut notify: Option<Notifier> = None;
+    let mut verbosity: Option<Verbosity> = None;
+    let mut log_json = false;
+
+    while let Some(arg) = raw_args.next() {
+        match arg.as_str() {
+            "export" => export = true,
+            "stats" => stats = true,
+            "log" => log = true,
+            "season" => season_command = true,
+            "--year" => {
+                year = Some(raw_args.next().and_then(|year| year.parse::<i32>().ok())
+                                    .ok_or("--year expects a year")?);
+            },
+            "--season" => season = Some(raw_args.next().ok_or("--season expects a season")?.parse()?),
+            "--today" => today = true,
+            "--since" => {
+                since_hours = Some(raw_args.next().and_then(|hours| hours.parse::<u64>().ok())
+                                           .ok_or("--since expects a number of hours")?);
+            },
+            "--output" => output = Some(PathBuf::from(raw_args.next().ok_or("--output expects a file")?)),
+            "--svg" => svg = Some(PathBuf::from(raw_args.next().ok_or("--svg expects a file")?)),
+            "--ics" => ics = Some(PathBuf::from(raw_args.next().ok_or("--ics expects a file")?)),
+            "--user" => user = Some(raw_args.next().ok_or("--user expects a user name")?),
+            "--users" => {
+                users = raw_args.next().ok_or("--users expects user names")?
+                                .split(',').map(str::trim).filter(|name| !name.is_empty())
+                                .map(String::from).collect();
+                if users.is_empty() {
+                    return Err(String::from("--users expects user names"));
+                }
+            },
+            "--date-format" => {
+                date_format = Some(parse_date_format(raw_args.next().ok_or("--date-format expects dmy or mdy")?));
+            },
+            "--only-airing-today" => query = query.filter(Filter::AiringToday),
+            "--min-score" => {
+                let score = raw_args.next().and_then(|score| score.parse::<f32>().ok())
+                                    .filter(|score| score.is_finite())
+                                    .ok_or("--min-score expects a score")?;
+                query = query.filter(Filter::MinScore(score));
+            },
+            "--genre" => query = query.filter(Filter::Genre(raw_args.next().ok_or("--genre expects a genre")?)),
+            "--search" => {
+                let text = raw_args.next().filter(|text| !text.trim().is_empty()).ok_or("--search expects a title")?;
+                query = query.filter(Filter::Search(text));
+            },
+            "--sort" => query = query.sort_by(raw_args.next().ok_or("--sort expects a sort key")?.parse::<SortKey>()?),
+            "--notify" => notify = Some(raw_args.next().ok_or("--notify expects a target")?.parse()?),
+            "--status" => list_status = Some(parse_list_status(&raw_args.next().ok_or("--status expects a list")?)?),
+            "--record" => record = true,
+            "--offline" => offline = true,
+            "--dry-run" => dry_run = true,
+            "--ignore-robots" => ignore_robots = true,
+            "--scrape" => scrape = true,
+            "--metadata" => metadata = true,
+            "--no-cache" => no_cache = true,
+            "--no-history" => no_history = true,
+            "--live" => live = true,
+            "--tui" => tui = true,
+            "-v" | "--verbose" | "-q" | "--quiet" => {
+                let level = if matches!(arg.as_str(), "-v" | "--verbose") { Verbosity::Verbose } else { Verbosity::Quiet };
+                if verbosity.is_some_and(|verbosity| verbosity != level) {
+                    return Err(String::from("--verbose and --quiet cannot be combined"));
+                }
+                verbosity = Some(level);
+            },
+            "--log-json" => log_json = true,
+            "--http1" => http.http2 = false,
+            "--proxy" => {
+                let proxy = raw_args.next().ok_or("--proxy expects a url")?.parse::<ProxyConfig>()?;
+                if http.proxy == ProxyConfig::Direct {
+                    return Err(String::from("--proxy and --no-proxy cannot be combined"));
+                }
+                http.proxy = proxy;
+            },
+            "--no-proxy" => {
+                if matches!(http.proxy, ProxyConfig::Url(_)) {
+                    return Err(String::from("--proxy and --no-proxy cannot be combined"));
+                }
+                http.proxy = ProxyConfig::Direct;
+            },
+            "--theme" => theme = Some(raw_args.next().ok_or("--theme expects a theme name")?),
+            "--utc-offset" => {
+                utc_offset = Some(raw_args.next().and_then(|offset| offset.parse::<FixedOffset>().ok())
+                                          .ok_or("--utc-offset expects an offset like +05:30")?);
+            },
+            "--requests-per-second" => {
+                requests_per_second = Some(raw_args.next().and_then(|rate| rate.parse::<f64>().ok())
+                                                   .filter(|rate| rate.is_finite())
+                                                   .filter(|rate| RateLimiter::per_second(*rate).is_ok())
+                                                   .ok_or("--requests-per-second expects a positive number")?);
+            },
+            "--title" => title_language = raw_args.next().ok_or("--title expects a language")?.parse()?,
+            "--workers" => {
+                workers = Some(raw_args.next().and_then(|workers| workers.parse::<usize>().ok())
+                                       .filter(|workers| *workers > 0)
+                                       .ok_or("--workers expects a positive number")?);
+            },
+            "--max-per-host" => {
+                max_per_host = Some(raw_args.next().and_then(|max| max.parse::<usize>().ok())
+                                            .filter(|max| *max > 0)
+                                            .ok_or("--max-per-host expects a positive number")?);
+            },
+            "--watch" => {
+                let secs = raw_args.next().and_then(|minutes| minutes.parse::<u64>().ok())
+                                   .filter(|minutes| *minutes > 0)
+                                   .and_then(|minutes| minutes.checked_mul(60))
+                                   .ok_or("--watch expects a positive number of minutes")?;
+                watch = Some(Duration::from_secs(secs));
+            },
+            "--cache-days" => {
+                let secs = raw_args.next().and_then(|days| days.parse::<u64>().ok())
+                                   .and_then(|days| days.checked_mul(24 * 60 * 60))
+                                   .ok_or("--cache-days expects a number of days")?;
+                cache_ttl = Some(Duration::from_secs(secs));
+            },
+            "--timeout" => {
+                let secs = raw_args.next().and_then(|secs| secs.parse::<u64>().ok())
+                                   .ok_or("--timeout expects a number of seconds")?;
+                http.timeout = if secs == 0 { None } else { Some(Duration::from_secs(secs)) };
+            },
+            "--keep-alive" => {
+                let secs = raw_args.next().and_then(|secs| secs.parse::<u64>().ok())
+                                   .ok_or("--keep-alive expects a number of seconds")?;
+                http.pool_idle_timeout = Some(Duration::from_secs(secs));
+                http.tcp_keepalive = if secs == 0 { None } else { Some(Duration::from_secs(secs)) };
+            },
+            "--fixtures" => {
+                let dir = raw_args.next().ok_or("--fixtures expects a directory")?;
+                fixtures = Some(PathBuf::from(dir));
+            },
+            "-h" | "--help" => return Ok(Args { command: Some(Command::Help), ..Default::default() }),
+            other => return Err(format!("unknown argument {}\n\n{}", other, USAGE)),
+        }
+    }
+
+    if no_cache && cache_ttl.is_some() {
+        return Err(String::from("--no-cache and --cache-days cannot be combined"));
+    }
+    if [live, watch.is_some(), tui].iter().filter(|mode| **mode).count() > 1 {
+        return Err(String::from("--live, --watch and --tui cannot be combined"));
+    }
+    if output.is_some() && (tui || watch.is_some()) {
+        return Err(String::from("--output cannot be combined with --tui or --watch"));
+    }
+    if record && offline {
+        return Err(String::from("--record and --offline cannot be combined"));
+    }
+    if dry_run && (record || offline) {
+        return Err(String::from("--dry-run cannot be combined with --record or --offline"));
+    }
+    if user.is_some() && !users.is_empty() {
+        return Err(String::from("--user and --users cannot be combined"));
+    }
+    if users.len() > 1 && (live || watch.is_some() || tui || export || output.is_some()) {
+        return Err(String::from("--live, --watch, --tui, --output and export take a single --user"));
+    }
+    if [export, stats, log, season_command].iter().filter(|command| **command).count() > 1 {
+        return Err(String::from("export, stats, log and season cannot be combined"));
+    }
+    if (year.is_some() || season.is_some() || today) && !season_command {
+        return Err(String::from("--year, --season and --today are options of the season command"));
+    }
+    if season_command && (user.is_some() || !users.is_empty()) {
+        return Err(String::from("the season command crawls no user's list"));
+    }
+    if !query.is_empty() && (log || season_command || export || stats) {
+        return Err(String::from("--only-airing-today, --min-score, --genre, --search and --sort filter crawled lists"));
+    }
+    if notify.is_some() && (log || season_command || export || stats) {
+        return Err(String::from("--notify posts the entries of crawled lists"));
+    }
+    if since_hours.is_some() && !log {
+        return Err(String::from("--since is an option of the log command"));
+    }
+    if log {
+        command = Some(Command::Log { since_hours });
+    } else if season_command {
+        let (this_year, this_season) = Season::current();
+        command = Some(Command::Season { year: year.unwrap_or(this_year), season: season.unwrap_or(this_season), today });
+    } else if export {
+        if svg.is_none() && ics.is_none() {
+            return Err(String::from("export expects --svg <FILE> or --ics <FILE>"));
+        }
+        let user = user.or_else(|| users.pop()).ok_or("export expects --user <NAME>")?;
+        command = Some(Command::Export { svg, ics, user, date_format, list_status: list_status.unwrap_or_default() });
+    } else if stats {
+        if list_status.is_some() {
+            return Err(String::from("stats crawls every section of the list, --status does not apply"));
+        }
+        if users.len() > 1 {
+            return Err(String::from("stats takes a single --user"));
+        }
+        let user = user.or_else(|| users.pop()).ok_or("stats expects --user <NAME>")?;
+        command = Some(Command::Stats { user, date_format });
+    } else if svg.is_some() || ics.is_some() {
+        return Err(String::from("--svg and --ics are options of the export command"));
+    } else if let Some(user) = user {
+        command = Some(Command::Crawl { users: vec![user], date_format, list_status: list_status.unwrap_or_default() });
+    } else if !users.is_empty() {
+        command = Some(Command::Crawl { users, date_format, list_status: list_status.unwrap_or_default() });
+    } else if list_status.is_some() {
+        return Err(String::from("--status needs --user or --users"));
+    }
+    let fixtures = fixtures.or_else(|| config::data_dir().map(|dir| dir.join("fixtures")));
+
+    let mut args = Args { ignore_robots, http, no_cache, no_history, cache_ttl, live, tui, watch, output, command,
+                         max_per_host, workers, title_language, theme, scrape, metadata, utc_offset,
+                         requests_per_second, query, notify, verbosity: verbosity.unwrap_or_default(), log_json,
+                         ..Default::default() };
+    if dry_run {
+        args.transport = Transport::DryRun(Arc::new(|url: &str| println!("[dry-run] GET {}", url)));
+    } else if record || offline {
+        let dir = fixtures.ok_or("could not locate the fixture directory, pass --fixtures")?;
+        args.transport = if record { Transport::Record(dir) } else { Transport::Replay(dir) };
+    }
+    Ok(args)
+}
+
+
+#[cfg(test)]
+fn parse(args: &[&str]) -> Result<Args, String> {
+    parse_args(args.iter().map(|arg| String::from(*arg)))
+}
+
+#[test]
+fn test_cache_days() {
+    assert_eq!(parse(&["--cache-days", "7"]).unwrap().cache_ttl, Some(Duration::from_secs(7 * 24 * 60 * 60)));
+    assert!(parse(&["--cache-days", &u64::MAX.to_string()]).is_err());
+    assert!(parse(&["--cache-days", "seven"]).is_err());
+}
+
+#[test]
+fn test_watch_minutes() {
+    assert_eq!(parse(&["--user", "goksgie", "--watch", "15"]).unwrap().watch, Some(Duration::from_secs(15 * 60)));
+    assert!(parse(&["--user", "goksgie", "--watch", &u64::MAX.to_string()]).is_err());
+    assert!(parse(&["--user", "goksgie", "--watch", "0"]).is_err());
+}
+
+#[test]
+fn test_flags() {
+    let args = parse(&["--user", "goksgie", "--date-format", "mdy", "--status", "on-hold", "--workers", "8",
+                       "--utc-offset", "+05:30", "--no-history", "--http1", "-v"]).unwrap();
+    assert!(matches!(&args.command, Some(Command::Crawl { users, date_format: Some(format), list_status: ListStatus::OnHold })
+                     if users == &["goksgie"] && format == "2"));
+    assert_eq!(args.workers, Some(8));
+    assert_eq!(args.utc_offset, FixedOffset::east_opt(5 * 3600 + 1800));
+    assert!(args.no_history && !args.http.http2);
+    assert_eq!(args.verbosity, Verbosity::Verbose);
+
+    let args = parse(&["--users", "goksgie, fixture"]).unwrap();
+    assert!(matches!(&args.command, Some(Command::Crawl { users, .. }) if users == &["goksgie", "fixture"]));
+    assert!(matches!(parse(&["log", "--since", "24"]).unwrap().command, Some(Command::Log { since_hours: Some(24) })));
+    assert!(matches!(parse(&["--dry-run"]).unwrap().transport, Transport::DryRun(_)));
+    assert!(parse(&[]).unwrap().command.is_none());
+}
+
+#[test]
+fn test_invalid_flags() {
+    let err = parse(&["--frobnicate"]).unwrap_err();
+    assert!(err.starts_with("unknown argument --frobnicate\n\n"));
+    assert_eq!(parse(&["--user"]).unwrap_err(), "--user expects a user name");
+    assert_eq!(parse(&["--workers", "0"]).unwrap_err(), "--workers expects a positive number");
+    assert_eq!(parse(&["--user", "a", "--users", "b"]).unwrap_err(), "--user and --users cannot be combined");
+    assert_eq!(parse(&["-v", "-q"]).unwrap_err(), "--verbose and --quiet cannot be combined");
+    assert_eq!(parse(&["--since", "1"]).unwrap_err(), "--since is an option of the log command");
+    assert_eq!(parse(&["--user", "a", "--tui", "--output", "list.json"]).unwrap_err(),
+               "--output cannot be combined with --tui or --watch");
+    assert!(parse(&["--user", "a", "--watch", "5", "--output", "list.json"]).is_err());
+    assert_eq!(parse(&["--requests-per-second", "1e-300"]).unwrap_err(),
+               "--requests-per-second expects a positive number");
+}
+
+#[test]
+fn test_help() {
+    assert!(matches!(parse(&["--help"]).unwrap().command, Some(Command::Help)));
+    // help wins over the flags after it.
+    assert!(matches!(parse(&["-h", "--frobnicate"]).unwrap().command, Some(Command::Help)));
+}
@@ -0,0 +1,272 @@
+// High level entry point for library users: configure a client once and
+// query lists, schedules and anime pages through it, instead of wiring the
+// requester, the keyword trie and the caches together by hand.
+//
+//     let client = MalClient::builder().cache(true)
+//                                      .rate_limit(Duration::from_millis(500))
+//                                      .build();
+//     let schedule = client.schedule(&user)?;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::FixedOffset;
+
+use crate::anime::{AnimeAttributes, UserAttributes};
+use crate::broadcast_cache::BroadcastCache;
+use crate::cancel::CancellationToken;
+use crate::config::REGISTERED_WORDS;
+use crate::error::CrawlerError;
+use crate::hooks::HookRegistry;
+use crate::progress::ProgressReporter;
+use crate::rate_limit::{HostLimiter, RateLimiter};
+use crate::requester::{self, AnimeDetails, AnimePageCache, CrawlOptions, CrawlResult, PageParser, RetryPolicy, Season,
+                       SeasonalAnime, UserProfile};
+use crate::requester::api::ApiAuth;
+use crate::schedule::WeeklySchedule;
+use crate::transport::Transport;
+use crate::trie::Trie;
+
+pub struct MalClient {
+    registered_words: Trie,
+    options: CrawlOptions,
+}
+
+#[derive(Default)]
+pub struct MalClientBuilder {
+    options: CrawlOptions,
+    cache: bool,
+    rate_limit: Option<Duration>,
+}
+
+impl MalClientBuilder {
+    /// where pages are fetched from, the network by default.
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.options.transport = transport;
+        self
+    }
+
+    /// share anime pages between every call of the client, so each page is
+    /// fetched at most once during its lifetime.
+    pub fn cache(mut self, enabled: bool) -> Self {
+        self.cache = enabled;
+        self
+    }
+
+    /// send at most one request per `interval`.
+    pub fn rate_limit(mut self, interval: Duration) -> Self {
+        self.rate_limit = Some(interval);
+        self
+    }
+
+    /// fetch pages from the host at the url instead of MAL, e.g. a local
+    /// server serving fixtures.
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.options.base_url = Some(String::from(url));
+        self
+    }
+
+    /// how requests MAL rate limited or could not serve are retried.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.options.retry = policy;
+        self
+    }
+
+    /// fetch anime pages with `workers` workers, NUM_WORKERS by default.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.options.workers = Some(workers);
+        self
+    }
+
+    /// allow at most `max` requests in flight to each host at once.
+    pub fn max_requests_per_host(mut self, max: usize) -> Self {
+        self.options.host_limit = Some(Arc::new(HostLimiter::new(max)));
+        self
+    }
+
+    /// hours between the local time and JST, detected from the host's
+    /// timezone by default.
+    pub fn time_diff_to_jst(mut self, hours: i32) -> Self {
+        self.options.time_diff_to_jst = Some(hours);
+        self
+    }
+
+    /// offset of the local time from UTC, for timezones whose offset is
+    /// not a whole number of hours. Detected from the host by default.
+    pub fn utc_offset(mut self, offset: FixedOffset) -> Self {
+        self.options.utc_offset = Some(offset);
+        self
+    }
+
+    /// persist broadcast hours of anime pages between runs.
+    pub fn broadcast_cache(mut self, cache: Arc<BroadcastCache>) -> Self {
+        self.options.broadcast_cache = Some(cache);
+        self
+    }
+
+    /// read lists from the official MAL API, falling back to scraping the
+    /// list page if a request fails.
+    pub fn api(mut self, auth: ApiAuth) -> Self {
+        self.options.api = Some(auth);
+        self
+    }
+
+    /// fetch the page of every entry for its Japanese title.
+    pub fn japanese_titles(mut self, enabled: bool) -> Self {
+        self.options.japanese_titles = enabled;
+        self
+    }
+
+    /// fetch the page of every entry for its genres, studios, source
+    /// material and community score.
+    pub fn metadata(mut self, enabled: bool) -> Self {
+        self.options.metadata = enabled;
+        self
+    }
+
+    /// checkpoint list crawls so interrupted ones resume.
+    pub fn resume(mut self, enabled: bool) -> Self {
+        self.options.resume = enabled;
+        self
+    }
+
+    /// crawl paths disallowed by robots.txt and ignore its crawl delay.
+    pub fn ignore_robots(mut self, ignore: bool) -> Self {
+        self.options.ignore_robots = ignore;
+        self
+    }
+
+    pub fn hooks(mut self, hooks: HookRegistry) -> Self {
+        self.options.hooks = hooks;
+        self
+    }
+
+    /// reporter told about the parsed list, fetched anime pages and retries
+    /// of every crawl.
+    pub fn progress(mut self, progress: Arc<dyn ProgressReporter>) -> Self {
+        self.options.progress = Some(progress);
+        self
+    }
+
+    /// parser the fields of anime pages are found with, instead of
+    /// MarkupParser.
+    pub fn parser(mut self, parser: Arc<dyn PageParser>) -> Self {
+        self.options.parser = Some(parser);
+        self
+    }
+
+    /// token that aborts every crawl of the client when cancelled.
+    pub fn cancel(mut self, cancel: CancellationToken) -> Self {
+        self.options.cancel = cancel;
+        self
+    }
+
+    pub fn build(self) -> MalClient {
+        let mut options = self.options;
+        if self.cache {
+            options.page_cache = Some(Arc::new(AnimePageCache::new()));
+        }
+        options.rate_limit = self.rate_limit.map(|interval| Arc::new(RateLimiter::new(interval)));
+
+        let registered_words = REGISTERED_WORDS.to_vec();
+        MalClient { registered_words: Trie::new(Some(&registered_words)), options }
+    }
+}
+
+impl MalClient {
+    pub fn builder() -> MalClientBuilder {
+        MalClientBuilder::default()
+    }
+
+    /// entries of the section of the user's list in `user.list_status`.
+    pub fn animelist(&self, user: &UserAttributes) -> Result<Vec<AnimeAttributes>, CrawlerError> {
+        requester::get_animelist_with(user, &self.registered_words, &self.options)
+    }
+
+    /// same as animelist, fetching the anime pages as concurrent tasks on
+    /// the caller's tokio runtime.
+    #[cfg(feature = "async")]
+    pub async fn animelist_async(&self, user: &UserAttributes) -> Result<Vec<AnimeAttributes>, CrawlerError> {
+        requester::async_client::get_animelist(user, &self.registered_words, &self.options).await
+    }
+
+    /// same as animelist, together with what the parser could not handle.
+    pub fn crawl(&self, user: &UserAttributes) -> Result<CrawlResult, CrawlerError> {
+        requester::crawl(user, &self.registered_words, &self.options)
+    }
+
+    /// airing entries of the user's list grouped by weekday.
+    pub fn schedule(&self, user: &UserAttributes) -> Result<WeeklySchedule, CrawlerError> {
+        self.animelist(user).map(WeeklySchedule::from_entries)
+    }
+
+    pub fn anime_details(&self, anime_id: i32) -> Result<AnimeDetails, CrawlerError> {
+        requester::get_anime_details(anime_id, &self.options)
+    }
+
+    /// same as crawl, reading the list page saved to the file instead of
+    /// fetching it. Anime pages are not fetched.
+    pub fn animelist_from_file(&self, path: &Path, user: &UserAttributes) -> Result<CrawlResult, CrawlerError> {
+        requester::get_animelist_from_file(path, user, &self.registered_words, &self.options)
+    }
+
+    /// same as anime_details, reading the anime page saved to the file.
+    pub fn anime_details_from_file(&self, path: &Path) -> Result<AnimeDetails, CrawlerError> {
+        requester::get_animepage_from_file(path, &self.options)
+    }
+
+    /// every anime starting in the given season.
+    pub fn seasonal(&self, year: i32, season: Season) -> Result<Vec<SeasonalAnime>, CrawlerError> {
+        requester::get_seasonal(year, season, &self.options)
+    }
+
+    /// the anime pages shared by the calls of the client, with how often
+    /// they were reused, None unless the builder enabled the cache.
+    pub fn page_cache(&self) -> Option<&AnimePageCache> {
+        self.options.page_cache.as_deref()
+    }
+
+    /// what the profile page of the user shows.
+    pub fn profile(&self, uname: &str) -> Result<UserProfile, CrawlerError> {
+        requester::get_user_profile(uname, &self.options)
+    }
+}
+
+
+#[test]
+fn test_client_replay() {
+    use crate::titles::TitleLanguage;
+
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let client = MalClient::builder().transport(Transport::Replay(fixtures))
+                                     .cache(true)
+                                     .japanese_titles(true)
+                                     .workers(1)
+                                     // in JST, the broadcast day is not shifted.
+                                     .utc_offset(FixedOffset::east_opt(9 * 3600).unwrap())
+                                     .build();
+    let mut user = UserAttributes::new(String::from("fixture"));
+    user.set_date_format(String::from("1")).unwrap();
+
+    // Mushishi is finished, so only Frieren is on the schedule.
+    let schedule = client.schedule(&user).unwrap();
+    assert_eq!(schedule.len(), 1);
+    let frieren = &schedule.iter().flat_map(|(_, entries)| entries).next().unwrap();
+    assert_eq!(frieren.preferred_title(TitleLanguage::Japanese), "葬送のフリーレン");
+
+    let details = client.anime_details(52991).unwrap();
+    assert_eq!(details.broadcast.as_deref(), Some("Fridays at 23:00 (JST)"));
+    assert_eq!(details.day_shift, 0);
+    assert_eq!(details.synonyms, vec!["Frieren at the Funeral", "Frieren: Beyond Journey's End"]);
+    assert_eq!(details.title_jp.as_deref(), Some("葬送のフリーレン"));
+    assert_eq!(details.genres, vec!["Adventure", "Drama", "Fantasy"]);
+    assert_eq!(details.studios, vec!["Madhouse"]);
+    assert_eq!((details.source.as_deref(), details.score), (Some("Manga"), Some(9.1)));
+
+    let season = client.seasonal(2023, Season::Fall).unwrap();
+    assert_eq!(season.len(), 3);
+
+    let profile = client.profile("fixture").unwrap();
+    assert_eq!((profile.mean_score, profile.favorites.len()), (Some(7.95), 2));
+}
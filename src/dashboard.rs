@@ -0,0 +1,197 @@
+// Terminal dashboard of a crawled list: the airing entries as a Monday to
+// Sunday grid, one column per weekday, with the details of the selected
+// entry below it. The arrow keys move the selection, r crawls the list
+// again and q quits.
+//
+// The dashboard only renders frames and interprets keys; the binary owns the
+// terminal, switching it to raw input with stty and drawing every frame over
+// the previous one.
+
+use std::io::{self, Read};
+
+use chrono::{Datelike, NaiveDateTime, Weekday};
+
+use crate::anime::AnimeAttributes;
+use crate::schedule::{self, WeeklySchedule};
+use crate::theme::{self, Theme};
+use crate::titles::{self, TitleLanguage};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Char(char),
+}
+
+/// What the dashboard asks of the binary after a key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Redraw,
+    Refresh,
+    Quit,
+}
+
+/// read a key from raw terminal input, None at the end of the input.
+/// Escape sequences other than the arrow keys are skipped.
+pub fn read_key<R: Read>(input: &mut R) -> io::Result<Option<Key>> {
+    let mut byte = [0u8];
+    loop {
+        if input.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        if byte[0] != 0x1b {
+            return Ok(Some(Key::Char(byte[0] as char)));
+        }
+        // arrow keys are sent as ESC [ A to ESC [ D.
+        let mut sequence = [0u8; 2];
+        if input.read(&mut sequence[..1])? == 0 || input.read(&mut sequence[1..])? == 0 {
+            return Ok(None);
+        }
+        let key = match &sequence {
+            b"[A" => Key::Up,
+            b"[B" => Key::Down,
+            b"[C" => Key::Right,
+            b"[D" => Key::Left,
+            _ => continue,
+        };
+        return Ok(Some(key));
+    }
+}
+
+const WEEK: [Weekday; 7] = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu,
+                            Weekday::Fri, Weekday::Sat, Weekday::Sun];
+
+#[derive(Debug, Clone)]
+pub struct Dashboard {
+    schedule: WeeklySchedule,
+    day: usize,
+    row: usize,
+}
+
+impl Dashboard {
+    /// the dashboard of the entries, today's column selected.
+    pub fn new(entries: Vec<AnimeAttributes>, today: Weekday) -> Self {
+        Dashboard { schedule: WeeklySchedule::from_entries(entries),
+                    day: today.num_days_from_monday() as usize, row: 0 }
+    }
+
+    /// show a new crawl of the list, keeping the selected day.
+    pub fn update(&mut self, entries: Vec<AnimeAttributes>) {
+        self.schedule = WeeklySchedule::from_entries(entries);
+        self.row = self.row.min(self.column(self.day).len().saturating_sub(1));
+    }
+
+    fn column(&self, day: usize) -> &[AnimeAttributes] {
+        self.schedule.on(WEEK[day])
+    }
+
+    pub fn selected(&self) -> Option<&AnimeAttributes> {
+        self.column(self.day).get(self.row)
+    }
+
+    pub fn handle(&mut self, key: Key) -> Action {
+        match key {
+            Key::Left | Key::Right => {
+                self.day = if key == Key::Left { (self.day + 6) % 7 } else { (self.day + 1) % 7 };
+                self.row = self.row.min(self.column(self.day).len().saturating_sub(1));
+            },
+            Key::Up => self.row = self.row.saturating_sub(1),
+            Key::Down => self.row = (self.row + 1).min(self.column(self.day).len().saturating_sub(1)),
+            Key::Char('r') => return Action::Refresh,
+            // ctrl-c arrives as a byte when the terminal does not handle it.
+            Key::Char('q') | Key::Char('\x03') => return Action::Quit,
+            Key::Char(_) => {},
+        }
+        Action::Redraw
+    }
+
+    /// the frame for a terminal `width` columns wide at the local time `now`.
+    pub fn render(&self, heading: &str, width: usize, now: NaiveDateTime,
+                  language: TitleLanguage, theme: &Theme) -> String {
+        let cell = (width.saturating_sub(WEEK.len()) / WEEK.len()).max(8);
+        let mut frame = format!("{}\n\n", theme::paint(theme.header, heading));
+
+        let today = now.weekday();
+        let names: Vec<String> = WEEK.iter().map(|day| {
+            let name = titles::pad_to_width(&day.to_string(), cell);
+            theme::paint(if *day == today { theme.airing } else { theme.header }, &name)
+        }).collect();
+        frame.push_str(&names.join(" "));
+        frame.push('\n');
+
+        let rows = (0..WEEK.len()).map(|day| self.column(day).len()).max().unwrap_or(0);
+        for row in 0..rows {
+            let cells: Vec<String> = (0..WEEK.len()).map(|day| {
+                let anime = match self.column(day).get(row) {
+                    Some(anime) => anime,
+                    None => return " ".repeat(cell),
+                };
+                let title = titles::pad_to_width(&titles::truncate_to_width(anime.preferred_title(language), cell), cell);
+                if (day, row) == (self.day, self.row) {
+                    // reverse video.
+                    format!("\x1b[7m{}\x1b[0m", title)
+                } else {
+                    theme::paint(if WEEK[day] == today { theme.airing } else { theme.title }, &title)
+                }
+            }).collect();
+            frame.push_str(&cells.join(" "));
+            frame.push('\n');
+        }
+        if rows == 0 {
+            frame.push_str("No airing entries.\n");
+        }
+
+        if let Some(anime) = self.selected() {
+            frame.push('\n');
+            frame.push_str(&theme::paint(theme.title, anime.preferred_title(language)));
+            frame.push('\n');
+            let when = match anime.next_airing(now) {
+                Some(next) => format!("{} airs in {}", next.format("%a %H:%M"),
+                                      schedule::format_countdown(next - now)),
+                None => format!("airs on {}", WEEK[self.day]),
+            };
+            frame.push_str(&theme::paint(theme.time, &when));
+            frame.push('\n');
+            let episodes = if anime.num_episodes > 0 { anime.num_episodes.to_string() } else { String::from("?") };
+            frame.push_str(&format!("watched {} of {} episodes, started {}\n", anime.num_watched_episodes,
+                                    episodes, anime.start_date));
+        }
+        frame
+    }
+}
+
+
+#[test]
+fn test_dashboard() {
+    let mut input: &[u8] = b"\x1b[C\x1b[Dq\x1b[Zr";
+    let keys: Vec<Key> = std::iter::from_fn(|| read_key(&mut input).unwrap()).collect();
+    assert_eq!(keys, vec![Key::Right, Key::Left, Key::Char('q'), Key::Char('r')]);
+
+    let mut frieren = AnimeAttributes::new();
+    frieren.title = String::from("Sousou no Frieren");
+    frieren.is_airing = true;
+    frieren.set_airing_weekday(Weekday::Fri);
+    let mut mushishi = frieren.clone();
+    mushishi.title = String::from("Mushishi Zoku Shou");
+    let mut dashboard = Dashboard::new(vec![frieren, mushishi], Weekday::Thu);
+    assert!(dashboard.selected().is_none());
+    assert_eq!(dashboard.handle(Key::Right), Action::Redraw);
+    assert_eq!(dashboard.handle(Key::Down), Action::Redraw);
+    assert_eq!(dashboard.selected().map(|anime| anime.title.as_str()), Some("Mushishi Zoku Shou"));
+    // the selection stays on the last entry of the column.
+    dashboard.handle(Key::Down);
+    assert_eq!(dashboard.selected().map(|anime| anime.title.as_str()), Some("Mushishi Zoku Shou"));
+    assert_eq!(dashboard.handle(Key::Char('r')), Action::Refresh);
+    assert_eq!(dashboard.handle(Key::Char('q')), Action::Quit);
+
+    let plain = Theme::named("plain").unwrap();
+    let now = chrono::NaiveDate::from_ymd_opt(2023, 10, 5).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    let frame = dashboard.render("Weekly schedule", 70, now, TitleLanguage::Romaji, &plain);
+    let lines: Vec<&str> = frame.lines().collect();
+    assert!(lines[2].starts_with("Mon       Tue"));
+    assert!(lines[3].contains("Sousou n…"));
+    assert!(frame.contains("\x1b[7mMushishi…"));
+    assert!(frame.contains("airs on Fri"));
+}
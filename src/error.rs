@@ -0,0 +1,106 @@
+// Crate-wide error type, so callers can tell a network failure from a parse
+// failure without downcasting a Box<dyn Error>.
+
+use std::io;
+use std::time::Duration;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CrawlerError {
+    #[cfg(feature = "http")]
+    #[error("request to {url} failed: {source}")]
+    Request {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("request to {url} failed with status {status}")]
+    Status {
+        url: String,
+        status: u16,
+        /// how long the server asked the crawler to wait before retrying.
+        retry_after: Option<Duration>,
+    },
+
+    #[error("malformed JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("could not parse date {value:?}: {source}")]
+    ParseDate {
+        value: String,
+        #[source]
+        source: chrono::ParseError,
+    },
+
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("invalid date format {0:?}, expected 1 (day-month-year) or 2 (month-day-year)")]
+    InvalidDateFormat(String),
+
+    #[error("invalid list status {0:?}, expected 1 (watching), 2 (completed), 3 (on hold), 4 (dropped), 6 (plan to watch) or 7 (all)")]
+    InvalidListStatus(String),
+
+    #[error("crawl was cancelled")]
+    Cancelled,
+
+    #[error("unsupported list layout ({0}), please update the crawler")]
+    UnsupportedLayout(String),
+
+    #[error("there is no MAL user named {0}")]
+    UserNotFound(String),
+
+    #[error("the list of {0} is private")]
+    ListPrivate(String),
+
+    #[error("robots.txt disallows crawling {0}")]
+    Disallowed(String),
+
+    #[error("cannot fetch {0}, the crawler was built without the http feature")]
+    NetworkDisabled(String),
+
+    #[error("invalid {path}, line {line}: {reason}")]
+    InvalidConfig { path: String, line: usize, reason: String },
+
+    #[error("invalid rate of {0} requests per second, its interval is too long or not positive")]
+    InvalidRate(f64),
+
+    #[error("unexpected response from the MAL API: {0}")]
+    Api(String),
+}
+
+impl CrawlerError {
+    #[cfg(feature = "http")]
+    pub fn request(url: &str, source: reqwest::Error) -> Self {
+        CrawlerError::Request { url: String::from(url), source }
+    }
+
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        CrawlerError::Io { context: context.into(), source }
+    }
+
+    /// true if the same request may well succeed a little later: the
+    /// server rate limited or was overloaded, or the request timed out.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            CrawlerError::Status { status, .. } => matches!(status, 429 | 500 | 502 | 503 | 504),
+            #[cfg(feature = "http")]
+            CrawlerError::Request { source, .. } => source.is_timeout() || source.is_connect(),
+            _ => false,
+        }
+    }
+
+    /// the Retry-After of the response, if the server sent one.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            CrawlerError::Status { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,215 @@
+// Renders crawl results for use outside the terminal.
+//
+// The weekly schedule is drawn as an SVG grid with a column per weekday,
+// so it can be posted as an image or converted to PNG with any SVG
+// renderer (e.g. `rsvg-convert schedule.svg -o schedule.png`).
+//
+// It is also written as an iCalendar file with a weekly recurring event per
+// airing entry, for calendar apps to import or subscribe to. Broadcasts are
+// announced in JST, which has no daylight saving time, so the events start
+// at a fixed time in UTC and calendar apps show them in their own timezone.
+
+use chrono::{DateTime, Utc, Weekday};
+
+use crate::anime::AnimeAttributes;
+use crate::schedule::WeeklySchedule;
+use crate::theme::{Rgb, Theme};
+use crate::titles::{self, TitleLanguage};
+
+const COLUMN_WIDTH: usize = 220;
+const HEADER_HEIGHT: usize = 70;
+const ENTRY_HEIGHT: usize = 54;
+const PADDING: usize = 10;
+// Japanese titles count two columns per character.
+const MAX_TITLE_COLUMNS: usize = 30;
+
+fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;")
+         .replace('<', "&lt;")
+         .replace('>', "&gt;")
+         .replace('"', "&quot;")
+         .replace('\'', "&apos;")
+}
+
+/// color of a role in the SVG, white for roles the theme leaves alone.
+fn fill(color: Option<Rgb>) -> String {
+    color.map_or(String::from("#ffffff"), |color| color.hex())
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+/// Draw the schedule as an SVG grid in the colors of the theme, entries of a
+/// day ordered by the time they air at and titled in the given language.
+/// Times are local, entries without a known time go last.
+pub fn schedule_svg(schedule: &WeeklySchedule, title: &str, language: TitleLanguage, theme: &Theme) -> String {
+    let rows = schedule.iter().map(|(_, entries)| entries.len()).max().unwrap_or(0).max(1);
+    let width = COLUMN_WIDTH * 7;
+    let height = HEADER_HEIGHT + rows * ENTRY_HEIGHT + PADDING;
+
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+                           viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">\n",
+                          w = width, h = height);
+    svg.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"#1b1d24\"/>\n", width, height));
+    svg.push_str(&format!("<text x=\"{}\" y=\"24\" font-size=\"18\" fill=\"{}\">{}</text>\n",
+                          PADDING, fill(theme.title), escape_xml(title)));
+
+    for (column, (weekday, entries)) in schedule.iter().enumerate() {
+        let x = column * COLUMN_WIDTH;
+        svg.push_str(&format!("<text x=\"{}\" y=\"56\" font-size=\"15\" font-weight=\"bold\" \
+                               fill=\"{}\">{}</text>\n", x + PADDING, fill(theme.header), weekday_name(weekday)));
+
+        let mut entries: Vec<_> = entries.iter().collect();
+        entries.sort_by_key(|anime| (anime.airing_time.is_none(), anime.airing_time));
+        for (row, anime) in entries.into_iter().enumerate() {
+            let y = HEADER_HEIGHT + row * ENTRY_HEIGHT;
+            svg.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"6\" \
+                                   fill=\"#2b2f3a\"/>\n",
+                                  x + PADDING / 2, y, COLUMN_WIDTH - PADDING, ENTRY_HEIGHT - PADDING / 2));
+            let time = anime.airing_time.map_or(String::new(), |time| time.format("%H:%M").to_string());
+            svg.push_str(&format!("<text x=\"{}\" y=\"{}\" font-size=\"12\" fill=\"{}\">{}</text>\n",
+                                  x + PADDING, y + 18, fill(theme.time), time));
+            let color = if anime.is_airing_today() { theme.airing.or(theme.title) } else { theme.title };
+            svg.push_str(&format!("<text x=\"{}\" y=\"{}\" font-size=\"13\" fill=\"{}\">{}</text>\n",
+                                  x + PADDING, y + 38, fill(color), escape_xml(&titles::truncate_to_width(anime.preferred_title(language), MAX_TITLE_COLUMNS))));
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// How long the events of episodes last, most air in a half hour slot.
+const EPISODE_MINUTES: i64 = 30;
+// Content lines longer than this many bytes are folded.
+const MAX_LINE_BYTES: usize = 75;
+
+/// escape the characters iCalendar text values give a meaning to.
+fn escape_ics(value: &str) -> String {
+    value.replace('\\', "\\\\")
+         .replace(';', "\\;")
+         .replace(',', "\\,")
+         .replace('\n', "\\n")
+}
+
+/// append a content line, folded into lines of at most MAX_LINE_BYTES
+/// bytes that continue with a space, without splitting a character.
+fn push_line(ics: &mut String, line: &str) {
+    let mut rest = line;
+    let mut limit = MAX_LINE_BYTES;
+    while rest.len() > limit {
+        let mut split = limit;
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+        ics.push_str(&rest[..split]);
+        ics.push_str("\r\n ");
+        rest = &rest[split..];
+        // the leading space counts towards the continuation line.
+        limit = MAX_LINE_BYTES - 1;
+    }
+    ics.push_str(rest);
+    ics.push_str("\r\n");
+}
+
+fn ics_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Write the airing entries with a known broadcast slot as an iCalendar
+/// calendar of weekly recurring events, starting with the next broadcast
+/// after `now` and titled in the given language.
+pub fn schedule_ics(entries: &[AnimeAttributes], name: &str, language: TitleLanguage, now: DateTime<Utc>) -> String {
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//anime-crawler//airing schedule//EN");
+    push_line(&mut ics, &format!("X-WR-CALNAME:{}", escape_ics(name)));
+    for anime in entries.iter().filter(|anime| anime.is_airing) {
+        let start = match anime.broadcast.as_ref().and_then(|broadcast| broadcast.next_airing(now)) {
+            Some(start) => start,
+            None => continue,
+        };
+        let episodes = if anime.num_episodes > 0 { anime.num_episodes.to_string() } else { String::from("?") };
+        push_line(&mut ics, "BEGIN:VEVENT");
+        push_line(&mut ics, &format!("UID:anime-{}@anime-crawler", anime.id));
+        push_line(&mut ics, &format!("DTSTAMP:{}", ics_time(now)));
+        push_line(&mut ics, &format!("DTSTART:{}", ics_time(start)));
+        push_line(&mut ics, &format!("DTEND:{}", ics_time(start + chrono::Duration::minutes(EPISODE_MINUTES))));
+        push_line(&mut ics, "RRULE:FREQ=WEEKLY");
+        push_line(&mut ics, &format!("SUMMARY:{}", escape_ics(anime.preferred_title(language))));
+        push_line(&mut ics, &format!("DESCRIPTION:{}", escape_ics(&format!("Watched {} of {} episodes",
+                                                                            anime.num_watched_episodes, episodes))));
+        push_line(&mut ics, &format!("URL:https://myanimelist.net/anime/{}", anime.id));
+        push_line(&mut ics, "END:VEVENT");
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+
+#[test]
+fn test_schedule_svg() {
+    use crate::anime::AnimeAttributes;
+
+    let mut anime = AnimeAttributes::new();
+    anime.is_airing = true;
+    anime.update_airing_day(5);
+    anime.title = String::from("Kusuriya no Hitorigoto <Apothecary> & more");
+    anime.airing_time = chrono::NaiveTime::from_hms_opt(17, 0, 0);
+    anime.title_jp = String::from("薬屋のひとりごと");
+    let schedule = WeeklySchedule::from_entries(vec![anime]);
+    let theme = Theme::named("default").unwrap();
+    let svg = schedule_svg(&schedule, "Fall 2023", TitleLanguage::Romaji, &theme);
+
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains(">Friday</text>"));
+    assert!(svg.contains("fill=\"#f0c674\">17:00</text>"));
+    assert!(svg.contains("Kusuriya no Hitorigoto &lt;Apoth…"));
+    let svg = schedule_svg(&schedule, "Fall 2023", TitleLanguage::Japanese, &Theme::default());
+    assert!(svg.contains(">薬屋のひとりごと</text>"));
+}
+
+#[test]
+fn test_schedule_ics() {
+    use crate::anime::BroadcastSchedule;
+    use chrono::{NaiveTime, TimeZone};
+
+    let mut anime = AnimeAttributes::new();
+    anime.id = 54492;
+    anime.is_airing = true;
+    anime.title = String::from("Kusuriya no Hitorigoto; Apothecary, Diaries");
+    anime.num_watched_episodes = 3;
+    anime.num_episodes = 24;
+    anime.broadcast = Some(BroadcastSchedule::jst(Weekday::Sun, NaiveTime::from_hms_opt(0, 55, 0).unwrap()));
+    let mut unknown = anime.clone();
+    unknown.broadcast = None;
+    let mut finished = anime.clone();
+    finished.is_airing = false;
+
+    // a thursday.
+    let now = Utc.with_ymd_and_hms(2023, 10, 5, 12, 0, 0).unwrap();
+    let ics = schedule_ics(&[anime.clone(), unknown, finished], "Watching", TitleLanguage::Romaji, now);
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+    // sunday 00:55 JST is saturday 15:55 UTC.
+    assert!(ics.contains("\r\nDTSTART:20231007T155500Z\r\nDTEND:20231007T162500Z\r\nRRULE:FREQ=WEEKLY\r\n"));
+    assert!(ics.contains("\r\nSUMMARY:Kusuriya no Hitorigoto\\; Apothecary\\, Diaries\r\n"));
+    assert!(ics.contains("\r\nDESCRIPTION:Watched 3 of 24 episodes\r\n"));
+
+    anime.title = "薬屋のひとりごと".repeat(5);
+    let ics = schedule_ics(&[anime], "Watching", TitleLanguage::Romaji, now);
+    for line in ics.split("\r\n") {
+        assert!(line.len() <= MAX_LINE_BYTES);
+    }
+    assert!(ics.contains("\r\n "));
+}
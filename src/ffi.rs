@@ -0,0 +1,139 @@
+// C ABI so non-Rust applications can embed the crawler.
+//
+// A list is crawled once with mal_crawl_list and then queried through the
+// returned handle. Every handle must be released with mal_list_free and every
+// string handed out with mal_string_free.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use serde::Serialize;
+
+use crate::anime::{AnimeAttributes, UserAttributes};
+use crate::config::REGISTERED_WORDS;
+use crate::requester;
+use crate::trie::Trie;
+
+/// Opaque handle to a crawled anime list.
+pub struct MalAnimeList {
+    entries: Vec<AnimeAttributes>,
+}
+
+/// the fields of an entry handed out as JSON.
+#[derive(Serialize)]
+struct AnimeJson<'a> {
+    id: i32,
+    title: &'a str,
+    title_eng: &'a str,
+    title_jp: &'a str,
+    status: i32,
+    score: i32,
+    num_episodes: i32,
+    start_date: &'a str,
+    is_airing: bool,
+    airing_today: bool,
+}
+
+impl<'a> AnimeJson<'a> {
+    fn new(anime: &'a AnimeAttributes) -> Self {
+        AnimeJson { id: anime.id, title: &anime.title, title_eng: &anime.title_eng, title_jp: &anime.title_jp,
+                    status: anime.status, score: anime.score, num_episodes: anime.num_episodes,
+                    start_date: &anime.start_date, is_airing: anime.is_airing,
+                    airing_today: anime.is_airing_today() }
+    }
+}
+
+/// Crawls the watching list of the given user. `date_format` follows the
+/// interactive prompt: 1 for day-month-year, 2 for month-day-year.
+/// Returns null if the arguments are invalid or the crawl fails.
+///
+/// # Safety
+///
+/// `uname` must be a valid, NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn mal_crawl_list(uname: *const c_char, date_format: c_int) -> *mut MalAnimeList {
+    if uname.is_null() || !(1..=2).contains(&date_format) {
+        return ptr::null_mut();
+    }
+    let uname = match CStr::from_ptr(uname).to_str() {
+        Ok(uname) => String::from(uname),
+        Err(_) => return ptr::null_mut(),
+    };
+
+    // a panic must never unwind across the C boundary.
+    let crawl = panic::catch_unwind(AssertUnwindSafe(|| {
+        let registered_words = REGISTERED_WORDS.to_vec();
+        let registered_trie = Trie::new(Some(&registered_words));
+        let mut user_attrib = UserAttributes::new(uname);
+        user_attrib.set_date_format(date_format.to_string()).ok()?;
+        requester::get_animelist(&user_attrib, &registered_trie).ok()
+    }));
+
+    match crawl {
+        Ok(Some(entries)) => Box::into_raw(Box::new(MalAnimeList { entries })),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Returns a JSON array of the entries in `list` that are airing today.
+/// The result must be released with `mal_string_free`.
+///
+/// # Safety
+///
+/// `list` must be a handle returned by `mal_crawl_list` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn mal_airing_today_json(list: *const MalAnimeList) -> *mut c_char {
+    if list.is_null() {
+        return ptr::null_mut();
+    }
+    let airing_today: Vec<AnimeJson> = (*list).entries.iter()
+                                                  .filter(|anime| anime.is_airing_today())
+                                                  .map(AnimeJson::new)
+                                                  .collect();
+    match serde_json::to_string(&airing_today).ok().and_then(|json| CString::new(json).ok()) {
+        Some(json) => json.into_raw(),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Releases a handle returned by `mal_crawl_list`.
+///
+/// # Safety
+///
+/// `list` must be null or a handle returned by `mal_crawl_list` that has not
+/// been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn mal_list_free(list: *mut MalAnimeList) {
+    if !list.is_null() {
+        drop(Box::from_raw(list));
+    }
+}
+
+/// Releases a string returned by this module.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this module that has not been
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn mal_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+
+#[test]
+fn test_anime_json() {
+    let mut anime = AnimeAttributes::new();
+    anime.id = 38524;
+    anime.title = String::from("Re:Zero \"Season\" 2\n");
+    anime.title_jp = String::from("進撃の巨人");
+    let json: serde_json::Value = serde_json::from_str(&serde_json::to_string(&AnimeJson::new(&anime)).unwrap()).unwrap();
+    assert_eq!(json["id"], 38524);
+    assert_eq!(json["title"], "Re:Zero \"Season\" 2\n");
+    assert_eq!(json["title_jp"], "進撃の巨人");
+    assert_eq!(json["airing_today"], false);
+}
@@ -0,0 +1,183 @@
+// History of the crawls of a list: the entries found by the last crawl are
+// kept as a JSON snapshot in the data directory, and the next crawl is
+// compared with it, reporting the entries added to or removed from the list,
+// those MAL lists more episodes for and those that started or finished
+// airing since.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::anime::AnimeAttributes;
+use crate::config;
+use crate::error::CrawlerError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub id: i32,
+    pub title: String,
+    pub num_episodes: i32,
+    pub num_watched_episodes: i32,
+    pub is_airing: bool,
+}
+
+/// The entries of a list as one crawl found them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// unix timestamp of the crawl.
+    pub taken_at: i64,
+    pub entries: Vec<SnapshotEntry>,
+}
+
+impl Snapshot {
+    pub fn of(entries: &[AnimeAttributes], taken_at: i64) -> Self {
+        let entries = entries.iter().map(|anime| {
+            SnapshotEntry { id: anime.id, title: anime.title.clone(), num_episodes: anime.num_episodes,
+                            num_watched_episodes: anime.num_watched_episodes, is_airing: anime.is_airing }
+        }).collect();
+        Snapshot { taken_at, entries }
+    }
+
+    /// what changed from the `previous` snapshot to this one, in the order
+    /// of the entries, removed entries last.
+    pub fn changes_since(&self, previous: &Snapshot) -> Vec<Change> {
+        let before: HashMap<i32, &SnapshotEntry> = previous.entries.iter().map(|entry| (entry.id, entry)).collect();
+        let mut changes = Vec::new();
+        for entry in &self.entries {
+            let old = match before.get(&entry.id) {
+                Some(old) => old,
+                None => {
+                    changes.push(Change::Added { title: entry.title.clone() });
+                    continue;
+                }
+            };
+            if old.num_episodes != entry.num_episodes {
+                changes.push(Change::Episodes { title: entry.title.clone(), before: old.num_episodes,
+                                                after: entry.num_episodes });
+            }
+            if old.is_airing != entry.is_airing {
+                changes.push(Change::Airing { title: entry.title.clone(), airing: entry.is_airing });
+            }
+        }
+        let current: Vec<i32> = self.entries.iter().map(|entry| entry.id).collect();
+        changes.extend(previous.entries.iter().filter(|entry| !current.contains(&entry.id))
+                                      .map(|entry| Change::Removed { title: entry.title.clone() }));
+        changes
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added { title: String },
+    Removed { title: String },
+    /// MAL lists a different number of episodes, 0 while it is not known.
+    Episodes { title: String, before: i32, after: i32 },
+    /// the entry started airing, or finished if `airing` is false.
+    Airing { title: String, airing: bool },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added { title } => write!(f, "{} was added to the list", title),
+            Change::Removed { title } => write!(f, "{} was removed from the list", title),
+            Change::Episodes { title, before: 0, after } => write!(f, "{} now has {} episodes", title, after),
+            Change::Episodes { title, before, after } => {
+                write!(f, "{} now has {} episodes instead of {}", title, after, before)
+            },
+            Change::Airing { title, airing: true } => write!(f, "{} started airing", title),
+            Change::Airing { title, airing: false } => write!(f, "{} finished airing", title),
+        }
+    }
+}
+
+/// The snapshot file of a list.
+#[derive(Debug)]
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn open(path: PathBuf) -> Self {
+        History { path }
+    }
+
+    /// the history of the given user's list in the data directory.
+    pub fn for_user(uname: &str) -> Result<Self, CrawlerError> {
+        let dir = config::data_dir().ok_or_else(|| {
+            CrawlerError::io("could not locate the data directory",
+                             std::io::Error::from(std::io::ErrorKind::NotFound))
+        })?;
+        let uname: String = uname.chars()
+                                 .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+                                 .collect();
+        Ok(History::open(dir.join(format!("history-{}.json", uname))))
+    }
+
+    /// the snapshot of the last crawl, None before the first one.
+    pub fn last(&self) -> Result<Option<Snapshot>, CrawlerError> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(CrawlerError::io(format!("could not read {}", self.path.display()), err)),
+        };
+        serde_json::from_str(&contents).map(Some).map_err(|err| {
+            CrawlerError::io(format!("could not parse {}", self.path.display()),
+                             std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+        })
+    }
+
+    /// keep the snapshot as the last crawl, replacing the previous one.
+    pub fn save(&self, snapshot: &Snapshot) -> Result<(), CrawlerError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|err| {
+                CrawlerError::io(format!("could not create {}", dir.display()), err)
+            })?;
+        }
+        fs::write(&self.path, serde_json::to_string(snapshot)?).map_err(|err| {
+            CrawlerError::io(format!("could not write {}", self.path.display()), err)
+        })
+    }
+
+    /// save the snapshot and return it with the one it replaced, if any.
+    pub fn record(&self, snapshot: Snapshot) -> Result<Option<Snapshot>, CrawlerError> {
+        let previous = self.last()?;
+        self.save(&snapshot)?;
+        Ok(previous)
+    }
+}
+
+
+#[test]
+fn test_history() {
+    let mut frieren = AnimeAttributes::new();
+    frieren.id = 52991;
+    frieren.title = String::from("Sousou no Frieren");
+    frieren.is_airing = true;
+    let mut mushishi = AnimeAttributes::new();
+    mushishi.id = 24687;
+    mushishi.title = String::from("Mushishi Zoku Shou");
+    mushishi.num_episodes = 10;
+
+    let path = std::env::temp_dir().join(format!("malcrawler-history-{}.json", std::process::id()));
+    let history = History::open(path.clone());
+    assert_eq!(history.last().unwrap(), None);
+    assert_eq!(history.record(Snapshot::of(&[frieren.clone(), mushishi], 1_696_500_000)).unwrap(), None);
+
+    let mut kaiju = AnimeAttributes::new();
+    kaiju.id = 52588;
+    kaiju.title = String::from("Kaijuu 8-gou");
+    frieren.num_episodes = 28;
+    frieren.is_airing = false;
+    let current = Snapshot::of(&[frieren, kaiju], 1_696_600_000);
+    let previous = history.record(current.clone()).unwrap().unwrap();
+    assert_eq!(previous.taken_at, 1_696_500_000);
+    let changes: Vec<String> = current.changes_since(&previous).iter().map(Change::to_string).collect();
+    assert_eq!(changes, vec!["Sousou no Frieren now has 28 episodes", "Sousou no Frieren finished airing",
+                             "Kaijuu 8-gou was added to the list", "Mushishi Zoku Shou was removed from the list"]);
+    assert_eq!(history.last().unwrap(), Some(current));
+    fs::remove_file(path).unwrap();
+}
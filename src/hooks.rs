@@ -0,0 +1,82 @@
+// Hooks let users add their own behaviour to a crawl (extra notifiers,
+// filters, exporters) without touching the parsing core.
+
+use crate::anime::AnimeAttributes;
+
+/// Callbacks invoked by the requester while crawling a list. Every method
+/// has a default implementation, so a hook only overrides what it needs.
+pub trait CrawlHook: Send + Sync {
+    /// called for every entry parsed from the list page. Returning false
+    /// drops the entry from the crawl.
+    fn on_entry_parsed(&self, _anime: &AnimeAttributes) -> bool {
+        true
+    }
+
+    /// called for every entry that airs today, once its precise airing
+    /// day is known.
+    fn on_airing_today(&self, _anime: &AnimeAttributes) {}
+
+    /// called with the final result of the crawl.
+    fn on_crawl_finished(&self, _anime_list: &[AnimeAttributes]) {}
+}
+
+/// Set of hooks registered at runtime, invoked in registration order.
+#[derive(Default)]
+pub struct HookRegistry {
+    hooks: Vec<Box<dyn CrawlHook>>,
+}
+
+impl HookRegistry {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn register(&mut self, hook: Box<dyn CrawlHook>) {
+        self.hooks.push(hook);
+    }
+
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// returns true if every registered hook keeps the entry.
+    pub fn entry_parsed(&self, anime: &AnimeAttributes) -> bool {
+        self.hooks.iter().all(|hook| hook.on_entry_parsed(anime))
+    }
+
+    pub fn airing_today(&self, anime: &AnimeAttributes) {
+        for hook in self.hooks.iter() {
+            hook.on_airing_today(anime);
+        }
+    }
+
+    pub fn crawl_finished(&self, anime_list: &[AnimeAttributes]) {
+        for hook in self.hooks.iter() {
+            hook.on_crawl_finished(anime_list);
+        }
+    }
+}
+
+
+#[test]
+fn test_entry_filter() {
+    struct SkipUnscored;
+    impl CrawlHook for SkipUnscored {
+        fn on_entry_parsed(&self, anime: &AnimeAttributes) -> bool {
+            anime.score > 0
+        }
+    }
+
+    let mut registry = HookRegistry::new();
+    let mut anime = AnimeAttributes::new();
+    assert!(registry.entry_parsed(&anime));
+
+    registry.register(Box::new(SkipUnscored));
+    assert!(!registry.entry_parsed(&anime));
+    anime.score = 8;
+    assert!(registry.entry_parsed(&anime));
+}
@@ -0,0 +1,60 @@
+// Interner for strings that repeat across many entries, so a huge list
+// holds one heap copy of every distinct value instead of one per entry.
+// Only the start dates of the entries go through it.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, OnceLock};
+
+#[derive(Debug, Default)]
+pub struct Interner {
+    strings: Mutex<HashSet<Arc<str>>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// returns the shared copy of the given value, creating it on first use.
+    pub fn intern(&self, value: &str) -> Arc<str> {
+        let mut strings = match self.strings.lock() {
+            Ok(strings) => strings,
+            // the set is always left consistent, so a poisoned lock is safe.
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        if let Some(interned) = strings.get(value) {
+            return Arc::clone(interned);
+        }
+        let interned: Arc<str> = Arc::from(value);
+        strings.insert(Arc::clone(&interned));
+        interned
+    }
+
+    /// number of distinct strings interned so far.
+    pub fn len(&self) -> usize {
+        self.strings.lock().map(|strings| strings.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Interner shared by the whole process.
+pub fn global() -> &'static Interner {
+    static INTERNER: OnceLock<Interner> = OnceLock::new();
+    INTERNER.get_or_init(Interner::new)
+}
+
+
+#[test]
+fn test_intern() {
+    let interner = Interner::new();
+    let first = interner.intern("04-06-22");
+    let second = interner.intern("04-06-22");
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(interner.len(), 1);
+    interner.intern("10-01-22");
+    assert_eq!(interner.len(), 2);
+}
@@ -0,0 +1,55 @@
+// Library side of the crawler. The interactive binary in main.rs is a thin
+// loop over these modules, so they can also be embedded elsewhere; Client
+// (client::MalClient) is the entry point for that.
+
+pub mod trie;
+pub mod transport;
+pub mod anime;
+pub mod requester;
+pub mod config;
+pub mod cancel;
+pub mod checkpoint;
+pub mod broadcast_cache;
+pub mod error;
+pub mod hooks;
+pub mod progress;
+pub mod intern;
+pub mod local_files;
+pub mod titles;
+pub mod report;
+pub mod rate_limit;
+pub mod schedule;
+pub mod query;
+pub mod dashboard;
+pub mod export;
+pub mod output;
+pub mod audit;
+pub mod theme;
+pub mod watch;
+pub mod history;
+pub mod stats;
+pub mod client;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "http")]
+pub mod notify;
+
+#[cfg(feature = "rss")]
+pub mod rss;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "python")]
+pub mod python;
+
+// The types an embedding program needs, so that it can write
+// `anime_crawler::Client` instead of reaching into the modules.
+pub use client::{MalClient as Client, MalClientBuilder as ClientBuilder};
+pub use anime::{AnimeAttributes, BroadcastSchedule, ListStatus, UserAttributes};
+pub use error::CrawlerError;
+pub use requester::{AnimeDetails, CrawlOptions, CrawlResult, UserProfile};
+pub use requester::api::ApiAuth;
+pub use query::{Filter, Query, SortKey};
+pub use schedule::WeeklySchedule;
+pub use transport::Transport;
@@ -0,0 +1,182 @@
+// Matches video files in a local downloads folder against list entries, so
+// the output can tell which episodes are already on disk.
+//
+// Release names are parsed anitomy-style: bracketed groups such as the
+// release group, resolution and checksum are dropped, and the episode number
+// is the number following a " - " separator, or failing that the last
+// episode-looking token of the name.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::anime::AnimeAttributes;
+use crate::error::CrawlerError;
+use crate::titles::normalize_title;
+
+const VIDEO_EXTENSIONS: [&str; 5] = ["mkv", "mp4", "avi", "webm", "m4v"];
+
+/// parse tokens like 07, 07v2, E07, EP07 and S01E07 into an episode number.
+fn parse_episode_token(token: &str) -> Option<i32> {
+    let lowered = token.to_lowercase();
+    let mut number = lowered.as_str();
+
+    // version suffix of re-releases: 07v2
+    if let Some(pos) = number.rfind('v') {
+        if pos > 0 && number[pos + 1..].chars().all(|c| c.is_ascii_digit()) {
+            number = &number[..pos];
+        }
+    }
+
+    if let Some(pos) = number.find('e') {
+        let prefix = &number[..pos];
+        let season_prefix = prefix.len() > 1 && prefix.starts_with('s')
+                            && prefix[1..].chars().all(|c| c.is_ascii_digit());
+        if prefix.is_empty() || season_prefix {
+            number = number[pos + 1..].trim_start_matches('p');
+        }
+    }
+
+    if number.is_empty() || number.len() > 4 || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    number.parse::<i32>().ok()
+}
+
+/// Extract the title and episode number from a release file name such as
+/// "[SubsPlease] Sousou no Frieren - 07 (1080p) [ABCD1234].mkv".
+pub fn parse_filename(file_name: &str) -> Option<(String, i32)> {
+    let stem = match file_name.rfind('.') {
+        Some(pos) if file_name.len() - pos <= 5 => &file_name[..pos],
+        _ => file_name,
+    };
+
+    // drop everything enclosed in brackets or parentheses, and closers
+    // without an opener.
+    let mut cleaned = String::with_capacity(stem.len());
+    let mut depth: usize = 0;
+    for c in stem.chars() {
+        match c {
+            '[' | '(' | '{' => depth += 1,
+            ']' | ')' | '}' => depth = depth.saturating_sub(1),
+            '_' | '.' if depth == 0 => cleaned.push(' '),
+            c if depth == 0 => cleaned.push(c),
+            _ => {}
+        }
+    }
+
+    let tokens: Vec<&str> = cleaned.split_whitespace().collect();
+    let after_separator = (1..tokens.len()).rev()
+                                           .find(|&i| tokens[i - 1] == "-"
+                                                      && parse_episode_token(tokens[i]).is_some());
+    let episode_index = after_separator.or_else(|| {
+        (1..tokens.len()).rev().find(|&i| parse_episode_token(tokens[i]).is_some())
+    })?;
+    let episode = parse_episode_token(tokens[episode_index])?;
+
+    let mut title_tokens = &tokens[..episode_index];
+    while let Some((last, rest)) = title_tokens.split_last() {
+        let lowered = last.to_lowercase();
+        if *last == "-" || lowered == "episode" || lowered == "ep" {
+            title_tokens = rest;
+        } else {
+            break;
+        }
+    }
+
+    if title_tokens.is_empty() {
+        return None;
+    }
+    Some((title_tokens.join(" "), episode))
+}
+
+/// Episodes found on disk, grouped by normalized title.
+#[derive(Debug, Default)]
+pub struct DownloadIndex {
+    episodes: HashMap<String, Vec<i32>>,
+}
+
+impl DownloadIndex {
+    /// scan the given folder (and its sub folders) for video files.
+    pub fn scan(dir: &Path) -> Result<Self, CrawlerError> {
+        let mut index = DownloadIndex::default();
+        index.scan_dir(dir).map_err(|err| {
+            CrawlerError::io(format!("could not scan {}", dir.display()), err)
+        })?;
+        for episodes in index.episodes.values_mut() {
+            episodes.sort_unstable();
+            episodes.dedup();
+        }
+        Ok(index)
+    }
+
+    fn scan_dir(&mut self, dir: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.scan_dir(&path)?;
+                continue;
+            }
+
+            let is_video = path.extension()
+                               .and_then(|ext| ext.to_str())
+                               .map(|ext| VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+                               .unwrap_or(false);
+            let file_name = path.file_name().and_then(|name| name.to_str());
+            if let (true, Some(file_name)) = (is_video, file_name) {
+                if let Some((title, episode)) = parse_filename(file_name) {
+                    self.episodes.entry(normalize_title(&title)).or_default().push(episode);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// downloaded episodes of the given entry, matched by any of its
+    /// titles, in ascending order.
+    pub fn downloaded_episodes(&self, anime: &AnimeAttributes) -> &[i32] {
+        anime.titles()
+             .find_map(|title| self.episodes.get(&normalize_title(title)))
+             .map(|episodes| episodes.as_slice())
+             .unwrap_or(&[])
+    }
+}
+
+/// format sorted episode numbers compactly, e.g. "1-5, 7".
+pub fn format_episodes(episodes: &[i32]) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut index = 0;
+    while index < episodes.len() {
+        let start = episodes[index];
+        while index + 1 < episodes.len() && episodes[index + 1] == episodes[index] + 1 {
+            index += 1;
+        }
+        if episodes[index] == start {
+            ranges.push(start.to_string());
+        } else {
+            ranges.push(format!("{}-{}", start, episodes[index]));
+        }
+        index += 1;
+    }
+    ranges.join(", ")
+}
+
+
+#[test]
+fn test_parse_filename() {
+    assert_eq!(parse_filename("[SubsPlease] Sousou no Frieren - 07 (1080p) [ABCD1234].mkv"),
+               Some((String::from("Sousou no Frieren"), 7)));
+    assert_eq!(parse_filename("Mob_Psycho_100_-_05v2_[720p].mp4"),
+               Some((String::from("Mob Psycho 100"), 5)));
+    assert_eq!(parse_filename("Spy.x.Family.S02E03.1080p.mkv"),
+               Some((String::from("Spy x Family"), 3)));
+    assert_eq!(parse_filename("[Group] Only A Title.mkv"), None);
+    assert_eq!(parse_filename("Title) - 03.mkv"), Some((String::from("Title"), 3)));
+}
+
+#[test]
+fn test_format_episodes() {
+    assert_eq!(format_episodes(&[1, 2, 3, 5, 7, 8]), "1-3, 5, 7-8");
+    assert_eq!(format_episodes(&[]), "");
+}
@@ -0,0 +1,151 @@
+// Diagnostics of the binary. Warnings of the crawl go to stderr by default;
+// --verbose adds the timings of every step, --quiet keeps only errors, and
+// --log-json writes every event as a JSON line for log collectors, together
+// with the spans (list fetch, page fetch, ...) it happened in.
+
+use std::fmt;
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Verbosity {
+    /// errors only.
+    Quiet,
+    /// warnings, or what RUST_LOG asks for if it is set.
+    #[default]
+    Normal,
+    /// the debug events of the crawler, with timings of every step.
+    Verbose,
+}
+
+impl Verbosity {
+    fn filter(self) -> EnvFilter {
+        match self {
+            Verbosity::Quiet => EnvFilter::new("error"),
+            Verbosity::Normal => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+            Verbosity::Verbose => EnvFilter::new("warn,anime_crawler=debug"),
+        }
+    }
+}
+
+/// install the subscriber printing the events of the crawl to stderr.
+pub fn init(verbosity: Verbosity, json: bool) {
+    let builder = tracing_subscriber::fmt().with_env_filter(verbosity.filter()).with_writer(std::io::stderr);
+    if json {
+        builder.with_ansi(false).event_format(JsonFormat).init();
+    } else {
+        builder.init();
+    }
+}
+
+/// collects the fields of an event as JSON values.
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), Value::from(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+}
+
+/// Writes an event as a line of JSON, e.g.
+/// {"timestamp":"...","level":"WARN","target":"anime_crawler::requester",
+///  "fields":{"message":"no broadcast hour found","url":"..."},
+///  "spans":[{"name":"crawl_animelist","fields":"user=goksgie"}]}
+pub struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let mut fields = JsonFields(Map::new());
+        event.record(&mut fields);
+        // outermost span first, with its fields as the default formatter
+        // recorded them.
+        let spans: Vec<Value> = ctx.event_scope().into_iter().flat_map(|scope| scope.from_root()).map(|span| {
+            let mut object = Map::new();
+            object.insert(String::from("name"), Value::from(span.name()));
+            if let Some(span_fields) = span.extensions().get::<FormattedFields<N>>() {
+                if !span_fields.is_empty() {
+                    object.insert(String::from("fields"), Value::from(span_fields.to_string()));
+                }
+            }
+            Value::Object(object)
+        }).collect();
+        let metadata = event.metadata();
+        let line = json!({
+            "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "level": metadata.level().to_string(),
+            "target": metadata.target(),
+            "fields": fields.0,
+            "spans": spans,
+        });
+        writeln!(writer, "{}", line)
+    }
+}
+
+
+#[test]
+fn test_json_format() {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::fmt().with_ansi(false).event_format(JsonFormat)
+                                                  .with_writer(move || writer.clone()).finish();
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("list_fetch", url = "https://myanimelist.net/animelist/goksgie");
+        let _entered = span.enter();
+        tracing::warn!(bytes = 512u64, cached = false, "list page too large");
+    });
+
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    let line: Value = serde_json::from_str(output.trim()).unwrap();
+    assert_eq!(line["level"], "WARN");
+    assert_eq!(line["fields"], json!({"message": "list page too large", "bytes": 512, "cached": false}));
+    assert_eq!(line["spans"][0]["name"], "list_fetch");
+    assert_eq!(line["spans"][0]["fields"], "url=\"https://myanimelist.net/animelist/goksgie\"");
+}
@@ -1,37 +1,311 @@
-use std::io::prelude::*;
+mod cli;
+mod logging;
 
-mod trie;
-mod anime;
-mod requester;
-mod config;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, OnceLock};
 
-use trie::Trie;
-use anime::{UserAttributes, AnimeAttributes};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDateTime, Timelike, Utc};
+
+use anime_crawler::trie::Trie;
+use anime_crawler::anime::{AnimeAttributes, ListStatus, UserAttributes};
+use anime_crawler::schedule::{self, WeeklySchedule};
+use anime_crawler::dashboard::{self, Action, Dashboard};
+use anime_crawler::export;
+use anime_crawler::output;
+use anime_crawler::audit::{self, AuditLog};
+use anime_crawler::requester::{self, CrawlOptions, SeasonalAnime};
+use anime_crawler::requester::api::ApiAuth;
+use anime_crawler::config::{self, Config, ProxyConfig, CONFIG_FILE_NAME, REGISTERED_WORDS, DOWNLOADS_DIR_VAR,
+                            THEME_VAR, THEME_COLORS_VAR, API_CLIENT_ID_VAR, API_ACCESS_TOKEN_VAR};
+use anime_crawler::broadcast_cache::BroadcastCache;
+use anime_crawler::cancel::CancellationToken;
+use anime_crawler::error::CrawlerError;
+use anime_crawler::local_files::{self, DownloadIndex};
+use anime_crawler::http;
+use anime_crawler::notify::{self, Notifier};
+use anime_crawler::rate_limit::{HostLimiter, RateLimiter};
+use anime_crawler::titles::{self, TitleLanguage};
+use anime_crawler::theme::{self, Theme};
+use anime_crawler::transport::Transport;
+use anime_crawler::watch::{WatchEvent, Watcher};
+use anime_crawler::progress::{ProgressBar, ProgressReporter};
+use anime_crawler::history::{History, Snapshot};
+use anime_crawler::stats::{self, ListStats};
+use tracing::{debug, warn};
+#[cfg(feature = "rss")]
+use anime_crawler::{rss, config::{RSS_FEED_VAR, DEFAULT_RSS_FEED}};
 
 
 fn main() {
+    let args = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            println!("{}", message);
+            std::process::exit(2);
+        }
+    };
+    if let Some(cli::Command::Help) = args.command {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    // warnings of the crawl are printed by default, --verbose or
+    // RUST_LOG=debug shows the timings of every step.
+    logging::init(args.verbosity, args.log_json);
+
+    // --theme wins over MAL_THEME, NO_COLOR turns the default colors off.
+    let theme_name = args.theme.clone().or_else(|| std::env::var(THEME_VAR).ok()).unwrap_or_else(|| {
+        String::from(if std::env::var_os("NO_COLOR").is_some() { "plain" } else { "default" })
+    });
+    let theme = match Theme::load(&theme_name, std::env::var(THEME_COLORS_VAR).ok().as_deref()) {
+        Ok(theme) => theme,
+        Err(err) => {
+            println!("{}", err);
+            std::process::exit(2);
+        }
+    };
+
+    if let Some(cli::Command::Log { since_hours }) = &args.command {
+        show_audit_log(*since_hours);
+        return;
+    }
+
+    // options given on the command line win over config.toml.
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            println!("{}", err);
+            std::process::exit(2);
+        }
+    };
+
+    // --notify needs a webhook to post to.
+    let webhook = match (args.notify, &config.discord_webhook) {
+        (Some(Notifier::Discord), Some(webhook)) => Some(webhook.as_str()),
+        (Some(Notifier::Discord), None) => {
+            println!("--notify discord needs discord_webhook in {}", CONFIG_FILE_NAME);
+            std::process::exit(2);
+        },
+        (None, _) => None,
+    };
+
+    match AuditLog::open_default() {
+        // nothing has been fetched yet, so the log always gets installed.
+        Ok(log) => { let _ = audit::install(log); },
+        Err(err) => warn!(%err, "crawling without the audit log"),
+    }
+
+    // nothing has been fetched yet, so the configuration always applies.
+    let mut http_config = args.http.clone();
+    if let (ProxyConfig::System, Some(proxy)) = (&http_config.proxy, &config.proxy) {
+        http_config.proxy = proxy.clone();
+    }
+    let _ = http::configure(http_config);
+
     // there should be an infinite loop that accepts username
     // and constructs their watching animelist.
 
     // create a trie object and register key words that are valid for
     // this operation.
-    let registered_words = vec!["status", "score", "is_rewatching", 
-                                "anime_airing_status", "anime_id", "anime_title",
-                                "anime_title_eng",
-                                "anime_start_date_string", "anime_num_episodes"];
-    let registered_trie = Trie::new(Some(&registered_words)); 
-    
-    let mut u_name = String::new();
-    let mut date_format = String::new();
+    let registered_words = REGISTERED_WORDS.to_vec();
+    let registered_trie = Trie::new(Some(&registered_words));
+
+    let broadcast_cache = if args.no_cache {
+        None
+    } else {
+        let ttl = args.cache_ttl.unwrap_or(config.cache_ttl);
+        BroadcastCache::open_default(ttl).map(Arc::new).map_err(|err| {
+            warn!(%err, "crawling without the broadcast cache");
+        }).ok()
+    };
+    let utc_offset = args.utc_offset.or(config.utc_offset);
+    // what is today, and the time of day, follow the offset the airing
+    // days are shifted to.
+    let args = cli::Args { utc_offset, ..args };
+    let workers = Some(args.workers.unwrap_or(config.workers));
+    // the bars of concurrent crawls would draw over each other, and the
+    // dashboard owns the terminal.
+    let single_list = !matches!(&args.command, Some(cli::Command::Crawl { users, .. }) if users.len() > 1);
+    let progress: Option<Arc<dyn ProgressReporter>> = if std::io::stderr().is_terminal() && single_list && !args.tui {
+        Some(Arc::new(ProgressBar::new()))
+    } else {
+        None
+    };
+    let host_limit = args.max_per_host.map(|max| Arc::new(HostLimiter::new(max)));
+    let rate_limit = match args.requests_per_second.map(RateLimiter::per_second).transpose() {
+        Ok(rate_limit) => rate_limit.map(Arc::new),
+        Err(err) => {
+            println!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    // the user's token wins over the client id, it also sees private lists.
+    let api = if args.scrape {
+        None
+    } else {
+        std::env::var(API_ACCESS_TOKEN_VAR).ok().map(ApiAuth::AccessToken)
+            .or_else(|| std::env::var(API_CLIENT_ID_VAR).ok().map(ApiAuth::ClientId))
+    };
+
+    if let Some(cli::Command::Stats { user, date_format }) = &args.command {
+        let mut user_attrib = UserAttributes::new(user.clone());
+        if let Some(date_format) = date_format.as_ref().or(config.date_format.as_ref()) {
+            if let Err(err) = user_attrib.set_date_format(date_format.clone()) {
+                println!("{}", err);
+                std::process::exit(2);
+            }
+        }
+        user_attrib.list_status = ListStatus::All;
+        // the length of the episodes is read from the anime pages.
+        let crawl_options = CrawlOptions { transport: args.transport.clone(),
+                                           ignore_robots: args.ignore_robots,
+                                           broadcast_cache, metadata: true,
+                                           host_limit: host_limit.clone(), api: api.clone(),
+                                           rate_limit: rate_limit.clone(), utc_offset,
+                                           workers, progress: progress.clone(),
+                                           ..Default::default() };
+        match requester::get_animelist_with(&user_attrib, &registered_trie, &crawl_options) {
+            Ok(anime_list) => show_stats(user, &ListStats::from_entries(&anime_list), &theme),
+            Err(err) => {
+                println!("Error: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(cli::Command::Export { svg, ics, user, date_format, list_status }) = &args.command {
+        let mut user_attrib = UserAttributes::new(user.clone());
+        if let Some(date_format) = date_format.as_ref().or(config.date_format.as_ref()) {
+            if let Err(err) = user_attrib.set_date_format(date_format.clone()) {
+                println!("{}", err);
+                std::process::exit(2);
+            }
+        }
+        user_attrib.list_status = *list_status;
+        user_attrib.title_language = args.title_language;
+        let crawl_options = CrawlOptions { transport: args.transport.clone(),
+                                           ignore_robots: args.ignore_robots,
+                                           broadcast_cache, broadcast_times: true,
+                                           host_limit: host_limit.clone(), api: api.clone(),
+                                           rate_limit: rate_limit.clone(), utc_offset,
+                                           workers, progress: progress.clone(),
+                                           ..Default::default() };
+        let anime_list = match requester::get_animelist_with(&user_attrib, &registered_trie, &crawl_options) {
+            Ok(anime_list) => anime_list,
+            Err(err) => {
+                println!("Error: {}", err);
+                std::process::exit(1);
+            }
+        };
+        let title = format!("Weekly schedule of {}", user);
+        let mut files = Vec::new();
+        if let Some(ics) = ics {
+            files.push((ics, export::schedule_ics(&anime_list, &title, user_attrib.title_language, Utc::now())));
+        }
+        if let Some(svg) = svg {
+            files.push((svg, export::schedule_svg(&WeeklySchedule::from_entries(anime_list), &title,
+                                                  user_attrib.title_language, &theme)));
+        }
+        for (path, contents) in files {
+            if let Err(err) = std::fs::write(path, contents) {
+                println!("could not write {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+            println!("Schedule written to {}", path.display());
+        }
+        return;
+    }
+
+    let crawl_options = CrawlOptions { resume: true, transport: args.transport.clone(),
+                                       ignore_robots: args.ignore_robots,
+                                       broadcast_cache,
+                                       broadcast_times: args.live || args.tui,
+                                       metadata: args.metadata || args.query.needs_metadata(),
+                                       host_limit, api, rate_limit, utc_offset,
+                                       workers, progress,
+                                       cancel: interrupt_token().clone(), partial_results: true,
+                                       ..Default::default() };
+
+    if let Some(cli::Command::Season { year, season, today }) = &args.command {
+        match requester::get_seasonal(*year, *season, &crawl_options) {
+            Ok(entries) => show_season(&entries, *today, args.utc_offset, &theme),
+            Err(err) => {
+                println!("Error: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
+    if let Some(cli::Command::Crawl { users, date_format, list_status }) = &args.command {
+        let mut user_attribs = Vec::with_capacity(users.len());
+        for user in users {
+            let mut user_attrib = UserAttributes::new(user.clone());
+            if let Some(date_format) = date_format.as_ref().or(config.date_format.as_ref()) {
+                if let Err(err) = user_attrib.set_date_format(date_format.clone()) {
+                    println!("{}", err);
+                    std::process::exit(2);
+                }
+            }
+            user_attrib.list_status = *list_status;
+            user_attrib.title_language = args.title_language;
+            user_attribs.push(user_attrib);
+        }
+        if let [user_attrib] = user_attribs.as_slice() {
+            if let Err(err) = crawl_and_show(user_attrib, &registered_trie, &crawl_options, webhook, &args, &theme) {
+                println!("Error: {}", err);
+                std::process::exit(1);
+            }
+            return;
+        }
+
+        // the lists are crawled at once, then printed one after the other.
+        let results = interruptible(|| requester::get_animelists(&user_attribs, &registered_trie, &crawl_options));
+        let interrupted = crawl_options.cancel.is_cancelled();
+        if interrupted {
+            println!("\nInterrupted, showing what was crawled so far");
+        }
+        let mut failed = false;
+        for (user_attrib, result) in user_attribs.iter().zip(results) {
+            println!("\n{}", theme::paint(theme.header, &format!("List of {}", user_attrib.uname)));
+            match result {
+                Ok(anime_list) => {
+                    show_changes(&user_attrib.uname, &anime_list, &args, &theme);
+                    let anime_list = args.query.apply(anime_list, local_now(args.utc_offset), user_attrib.title_language);
+                    notify_airing(&user_attrib.uname, &anime_list, webhook, &args);
+                    show_profile(&user_attrib.uname, &crawl_options, &theme);
+                    show_list(anime_list, user_attrib, &args, &theme);
+                },
+                Err(err) => {
+                    println!("Error: {}", err);
+                    failed = true;
+                }
+            }
+        }
+        std::process::exit(if interrupted { 130 } else if failed { 1 } else { 0 });
+    }
+
+    // connect to MAL while the user is typing the first user name, later
+    // lists find the connection in the pool.
+    if matches!(args.transport, Transport::Live | Transport::Record(_)) {
+        requester::warm_up(&crawl_options);
+    }
     loop {
-        u_name = String::new();
-        date_format = String::new();
+        let mut u_name = String::new();
+        let mut date_format = String::new();
 
-        println!("Enter User Name: ");
+        match &config.user {
+            Some(user) => println!("Enter User Name, or press enter for {}: ", user),
+            None => println!("Enter User Name: "),
+        }
         match std::io::stdin().read_line(&mut u_name) {
             Ok(_) => {
                 u_name = String::from(u_name.trim());
+                if let (true, Some(user)) = (u_name.is_empty(), &config.user) {
+                    u_name = user.clone();
+                }
             },
             Err(err) => {
                 println!("user did not enter a valid input");
@@ -40,17 +314,28 @@ fn main() {
             }
         };
         let mut user_attrib = UserAttributes::new(u_name.clone());
-        println!("\nSelect a time format from following options:\n");
+        user_attrib.title_language = args.title_language;
+        println!("\nSelect a time format from following options, or press enter to detect it:\n");
         println!("1 -> Day - Month - Year \t 2 -> Month - Day - Year");
         println!("Example input for Day - Month - Year: 1");
         match std::io::stdin().read_line(&mut date_format) {
-            Ok(size) => {
+            Ok(_) => {
                 date_format = String::from(date_format.trim());
-                if date_format.len() != 1 {
+                if date_format.len() > 1 {
                     println!("User did not enter a valid input");
                     continue;
                 }
-                user_attrib.set_date_format(date_format.clone());
+                // left empty, the format of config.toml is used or guessed
+                // from the start dates of the list.
+                if date_format.is_empty() {
+                    date_format = config.date_format.clone().unwrap_or_default();
+                }
+                if !date_format.is_empty() {
+                    if let Err(err) = user_attrib.set_date_format(date_format.clone()) {
+                        println!("{}", err);
+                        continue;
+                    }
+                }
             },
             Err(err) => {
                 println!("user did not enter a valid input");
@@ -58,18 +343,441 @@ fn main() {
                 continue;
             }
         };
-        
-        // print the user's watch list with attributes.
-        match requester::get_animelist(&user_attrib, &registered_trie) {
-            Ok(anime_list) => {
-                println!("{:<150} {:<20} {:<10}", "Anime Name", "Airing Today", "Finished");
-                for anime_entry in anime_list {
-                    println!("{:<150} {:<20} {:<10}", anime_entry.title, anime_entry.is_airing_today(), anime_entry.is_finished());
+        let mut list_status = String::new();
+        println!("\nSelect the list to crawl, or press enter for the watching list:\n");
+        println!("1 -> Watching \t 2 -> Completed \t 3 -> On Hold");
+        println!("4 -> Dropped \t 6 -> Plan to Watch \t 7 -> All");
+        match std::io::stdin().read_line(&mut list_status) {
+            Ok(_) => {
+                let list_status = list_status.trim();
+                if !list_status.is_empty() {
+                    if let Err(err) = user_attrib.set_list_status(list_status) {
+                        println!("{}", err);
+                        continue;
+                    }
                 }
             },
             Err(err) => {
-                println!("Error: {:?}", err);
+                println!("user did not enter a valid input");
+                println!("Following error occured: {}", err);
+                continue;
+            }
+        };
+
+        // print the user's watch list with attributes.
+        if let Err(err) = crawl_and_show(&user_attrib, &registered_trie, &crawl_options, webhook, &args, &theme) {
+            println!("Error: {}", err);
+        }
+    }
+}
+
+/// the current time at the given offset from UTC, the local time if there
+/// is none.
+fn local_now(utc_offset: Option<FixedOffset>) -> NaiveDateTime {
+    match utc_offset {
+        Some(offset) => Utc::now().with_timezone(&offset).naive_local(),
+        None => Local::now().naive_local(),
+    }
+}
+
+/// Crawl the list of the user and print it, or keep watching it with
+/// --live and --watch.
+fn crawl_and_show(user_attrib: &UserAttributes, registered_trie: &Trie, crawl_options: &CrawlOptions,
+                  webhook: Option<&str>, args: &cli::Args, theme: &Theme) -> Result<(), CrawlerError> {
+    if let Some(interval) = args.watch {
+        watch_list(user_attrib, registered_trie, crawl_options, interval, webhook, args);
+    }
+    let anime_list = interruptible(|| requester::get_animelist_with(user_attrib, registered_trie, crawl_options));
+    if crawl_options.cancel.is_cancelled() {
+        match anime_list {
+            Ok(anime_list) => {
+                println!("\nInterrupted, showing the {} entries crawled so far", anime_list.len());
+                let anime_list = args.query.apply(anime_list, local_now(args.utc_offset), user_attrib.title_language);
+                show_list(anime_list, user_attrib, args, theme);
+            },
+            Err(_) => println!("\nInterrupted before the list was read"),
+        }
+        std::process::exit(130);
+    }
+    let anime_list = anime_list?;
+    show_changes(&user_attrib.uname, &anime_list, args, theme);
+    let anime_list = args.query.apply(anime_list, local_now(args.utc_offset), user_attrib.title_language);
+    notify_airing(&user_attrib.uname, &anime_list, webhook, args);
+    if args.tui {
+        return run_dashboard(user_attrib, anime_list, registered_trie, crawl_options, args, theme);
+    }
+    if let Some(path) = &args.output {
+        output::write_file(path, &anime_list)?;
+        println!("Entries written to {}", path.display());
+    }
+    if args.live {
+        show_countdowns(&user_attrib.uname, &anime_list, user_attrib.title_language, args.utc_offset, theme);
+    }
+    show_profile(&user_attrib.uname, crawl_options, theme);
+    show_list(anime_list, user_attrib, args, theme);
+    Ok(())
+}
+
+/// Record the crawled list in the history of the user and print what
+/// changed since the last crawl of it. Replayed and dry-run crawls are left
+/// out of the history.
+fn show_changes(uname: &str, anime_list: &[AnimeAttributes], args: &cli::Args, theme: &Theme) {
+    if args.no_history || !matches!(args.transport, Transport::Live | Transport::Record(_)) {
+        return;
+    }
+    let snapshot = Snapshot::of(anime_list, Local::now().timestamp());
+    let previous = match History::for_user(uname).and_then(|history| history.record(snapshot.clone())) {
+        Ok(Some(previous)) => previous,
+        Ok(None) => return,
+        Err(err) => {
+            warn!(%err, "crawling without the history");
+            return;
+        }
+    };
+    let changes = snapshot.changes_since(&previous);
+    if changes.is_empty() {
+        return;
+    }
+    let since = DateTime::from_timestamp(previous.taken_at, 0)
+        .map_or(String::new(), |time| time.with_timezone(&Local).format(" (%Y-%m-%d %H:%M)").to_string());
+    println!("{}", theme::paint(theme.header, &format!("Changes since the last crawl{}", since)));
+    for change in changes {
+        println!("    {}", change);
+    }
+    println!();
+}
+
+/// Post the entries of the user's list airing today to the webhook of
+/// --notify. Like the history, replayed and dry-run crawls are left out.
+fn notify_airing(uname: &str, anime_list: &[AnimeAttributes], webhook: Option<&str>, args: &cli::Args) {
+    let webhook = match webhook {
+        Some(webhook) if matches!(args.transport, Transport::Live | Transport::Record(_)) => webhook,
+        _ => return,
+    };
+    if let Some(message) = notify::discord_message(uname, anime_list, args.title_language) {
+        if let Err(err) = notify::post_discord(webhook, &message) {
+            warn!(%err, "could not post to Discord");
+        }
+    }
+}
+
+/// cancelled by Ctrl-C while an interruptible crawl runs.
+static INTERRUPT: OnceLock<CancellationToken> = OnceLock::new();
+
+fn interrupt_token() -> &'static CancellationToken {
+    INTERRUPT.get_or_init(CancellationToken::new)
+}
+
+#[cfg(unix)]
+extern "C" fn on_interrupt(_signal: libc::c_int) {
+    match INTERRUPT.get() {
+        // a second Ctrl-C does not wait for the requests in flight.
+        Some(token) if !token.is_cancelled() => token.cancel(),
+        _ => unsafe { libc::_exit(130) },
+    }
+}
+
+/// Run the crawl with Ctrl-C cancelling interrupt_token() instead of
+/// killing the process, so that the entries crawled so far can still be
+/// shown. Outside of the crawl, Ctrl-C quits as usual.
+fn interruptible<T>(crawl: impl FnOnce() -> T) -> T {
+    interrupt_token();
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGINT, on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t);
+    }
+    let result = crawl();
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+    }
+    result
+}
+
+/// Print the entries with whether they air today, and with the downloaded
+/// episodes and releases of the feed if those are set up.
+// args only matter for the feed of the rss feature.
+#[cfg_attr(not(feature = "rss"), allow(unused_variables))]
+fn show_list(anime_list: Vec<AnimeAttributes>, user_attrib: &UserAttributes, args: &cli::Args, theme: &Theme) {
+    let downloads = std::env::var_os(DOWNLOADS_DIR_VAR).and_then(|dir| {
+        DownloadIndex::scan(dir.as_ref()).map_err(|err| {
+            warn!(%err, "not showing the downloaded episodes");
+        }).ok()
+    });
+    #[cfg(feature = "rss")]
+    let feed_items = {
+        let feed_url = std::env::var(RSS_FEED_VAR).unwrap_or_else(|_| String::from(DEFAULT_RSS_FEED));
+        rss::fetch_feed(&args.transport, &feed_url).unwrap_or_else(|err| {
+            warn!(%err, "could not fetch the RSS feed");
+            Vec::new()
+        })
+    };
+
+    let now = local_now(args.utc_offset);
+    let header = format!("{:<150} {:<30} {:<10}", "Anime Name", "Next Episode", "Finished");
+    print!("{}", theme::paint(theme.header, &header));
+    if downloads.is_some() {
+        print!("{}", theme::paint(theme.header, &format!(" {:<20}", "Downloaded")));
+    }
+    println!();
+    for anime_entry in anime_list {
+        let title = anime_entry.display_title(user_attrib);
+        let color = if anime_entry.is_finished() {
+            theme.finished
+        } else if anime_entry.is_airing_today() {
+            theme.airing
+        } else {
+            theme.title
+        };
+        let row = format!("{} {:<30} {:<10}", titles::pad_to_width(title, 150),
+                          schedule::describe_next_episode(&anime_entry, now), anime_entry.is_finished());
+        print!("{}", theme::paint(color, &row));
+        if let Some(downloads) = &downloads {
+            let episodes = downloads.downloaded_episodes(&anime_entry);
+            print!(" {:<20}", local_files::format_episodes(episodes));
+        }
+        println!();
+
+        #[cfg(feature = "rss")]
+        if anime_entry.is_airing_today() {
+            if let Some((episode, releases)) = rss::latest_releases(&anime_entry, &feed_items) {
+                for release in releases {
+                    println!("    Episode {} -> {}", episode, release.link);
+                }
             }
         }
     }
 }
+
+/// Print the anime of a season, with the weekday they air on in JST. With
+/// `today`, only those airing today are printed.
+fn show_season(entries: &[SeasonalAnime], today: bool, utc_offset: Option<FixedOffset>, theme: &Theme) {
+    let minutes_to_jst = utc_offset.map_or_else(config::minutes_to_jst, config::minutes_to_jst_from);
+    let weekday = config::local_weekday(Utc::now(), minutes_to_jst);
+    let header = format!("{:<60} {:<10} {:<6} {:<30} {}", "Anime Name", "Airs on", "Score", "Studios", "Genres");
+    println!("{}", theme::paint(theme.header, &header));
+    for anime in entries {
+        let airs_today = anime.airing_weekday() == Some(weekday);
+        if today && !airs_today {
+            continue;
+        }
+        let day = anime.airing_weekday().map_or(String::from("-"), |day| day.to_string());
+        let score = anime.score.map_or(String::from("N/A"), |score| format!("{:.2}", score));
+        let row = format!("{} {:<10} {:<6} {} {}", titles::pad_to_width(&anime.title, 60), day, score,
+                          titles::pad_to_width(&anime.studios.join(", "), 30), anime.genres.join(", "));
+        println!("{}", theme::paint(if airs_today { theme.airing } else { theme.title }, &row));
+    }
+}
+
+/// Print a line about the user from their profile page, and their favorite
+/// anime. Nothing is printed if the profile could not be fetched.
+fn show_profile(uname: &str, crawl_options: &CrawlOptions, theme: &Theme) {
+    let profile = match requester::get_user_profile(uname, crawl_options) {
+        Ok(profile) => profile,
+        Err(err) => {
+            warn!(%err, "not showing the profile");
+            return;
+        }
+    };
+    let mut summary = vec![profile.uname.clone()];
+    if let Some(joined) = profile.joined {
+        summary.push(format!("joined {}", joined.format("%b %-d, %Y")));
+    }
+    if let Some(days) = profile.days_watched {
+        summary.push(format!("{:.1} days watched", days));
+    }
+    if let Some(score) = profile.mean_score {
+        summary.push(format!("mean score {:.2}", score));
+    }
+    println!("{}", theme::paint(theme.header, &summary.join(" | ")));
+    if !profile.favorites.is_empty() {
+        println!("Favorites: {}", profile.favorites.join(", "));
+    }
+    println!();
+}
+
+/// Print the statistics of the whole list of the user.
+fn show_stats(uname: &str, stats: &ListStats, theme: &Theme) {
+    println!("{}", theme::paint(theme.header, &format!("Statistics of the list of {}", uname)));
+    println!("{:<16} {}", "Entries", stats.entries);
+    for (status, count) in &stats.per_status {
+        println!("  {:<14} {}", status.api_name().unwrap_or("all").replace('_', " "), count);
+    }
+    let mean = stats.mean_score.map_or(String::from("-"), |score| format!("{:.2}", score));
+    println!("{:<16} {}", "Mean score", mean);
+    println!("{:<16} {}", "Episodes", stats.episodes_watched);
+    let mut time = stats::format_watch_time(stats.watch_seconds);
+    if stats.unknown_length > 0 {
+        time.push_str(&format!(" (without {} entries of unknown length)", stats.unknown_length));
+    }
+    println!("{:<16} {}", "Time watched", time);
+    println!("\n{}", theme::paint(theme.header, "Scores"));
+    for line in stats.histogram(40) {
+        println!("{}", line);
+    }
+}
+
+/// Print the requests recorded in the audit log, followed by their totals.
+fn show_audit_log(since_hours: Option<u64>) {
+    let path = match audit::default_path() {
+        Some(path) => path,
+        None => {
+            println!("could not locate the data directory");
+            std::process::exit(1);
+        }
+    };
+    let since = since_hours.map_or(0, |hours| {
+        let now = Local::now().timestamp().max(0) as u64;
+        now.saturating_sub(hours.saturating_mul(60 * 60))
+    });
+    let entries = match audit::read(&path, since) {
+        Ok(entries) => entries,
+        Err(err) => {
+            println!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    println!("{:<20} {:<7} {:>10} {:<6} Url", "Time", "Status", "Bytes", "Cache");
+    for entry in &entries {
+        let time = DateTime::from_timestamp(entry.timestamp as i64, 0)
+            .map_or(String::new(), |time| time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string());
+        let status = entry.status.map_or(String::from("-"), |status| status.to_string());
+        let cache = if entry.cache_hit { "hit" } else { "miss" };
+        println!("{:<20} {:<7} {:>10} {:<6} {}", time, status, entry.bytes, cache, entry.url);
+    }
+    let requests = entries.iter().filter(|entry| !entry.cache_hit).count();
+    let bytes: usize = entries.iter().map(|entry| entry.bytes).sum();
+    println!("\n{} requests, {} bytes downloaded, {} cache hits",
+             requests, bytes, entries.len() - requests);
+}
+
+/// Crawl the list of the user again every `interval` and notify about the
+/// entries airing today and changed episode counts, until the user quits.
+/// The webhook of --notify hears about the entries airing today whenever
+/// one of them is announced, which is once a day.
+fn watch_list(user_attrib: &UserAttributes, registered_trie: &Trie, crawl_options: &CrawlOptions,
+              interval: std::time::Duration, webhook: Option<&str>, args: &cli::Args) -> ! {
+    let mut watcher = Watcher::new(user_attrib.title_language);
+    println!("Watching the list of {} (Ctrl-C to quit)", user_attrib.uname);
+    loop {
+        match requester::get_animelist_with(user_attrib, registered_trie, crawl_options) {
+            Ok(anime_list) => {
+                let events = watcher.update(&anime_list, local_now(args.utc_offset).date());
+                for event in &events {
+                    notify_desktop(event);
+                }
+                if events.iter().any(|event| matches!(event, WatchEvent::AiringToday { .. })) {
+                    notify_airing(&user_attrib.uname, &anime_list, webhook, args);
+                }
+            },
+            Err(err) => println!("Error: {}", err),
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+/// print the event and show it as a desktop notification, if notify-send
+/// is available.
+fn notify_desktop(event: &WatchEvent) {
+    println!("{}", event);
+    let sent = Command::new("notify-send").arg("anime-crawler").arg(event.to_string())
+                                          .stdout(Stdio::null()).stderr(Stdio::null())
+                                          .status();
+    if let Err(err) = sent {
+        debug!(%err, "no desktop notification");
+    }
+}
+
+/// run stty on the terminal the crawler reads from, its output if it succeeded.
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty").args(args).stdin(Stdio::inherit()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok().map(|settings| String::from(settings.trim()))
+}
+
+/// Show the list in the dashboard until q is pressed, crawling it again on
+/// r. Keys are read unbuffered, the terminal settings are restored on exit.
+fn run_dashboard(user_attrib: &UserAttributes, anime_list: Vec<AnimeAttributes>, registered_trie: &Trie,
+                 crawl_options: &CrawlOptions, args: &cli::Args, theme: &Theme) -> Result<(), CrawlerError> {
+    let saved = stty(&["-g"]);
+    if saved.is_none() || stty(&["-icanon", "-echo", "-isig", "min", "1"]).is_none() {
+        println!("--tui needs an interactive terminal");
+        show_list(anime_list, user_attrib, args, theme);
+        return Ok(());
+    }
+    let width = stty(&["size"]).and_then(|size| size.split_whitespace().nth(1)?.parse::<usize>().ok())
+                               .unwrap_or(140);
+    let mut dashboard = Dashboard::new(anime_list, local_now(args.utc_offset).weekday());
+    let mut status = String::new();
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+    loop {
+        let now = local_now(args.utc_offset);
+        let heading = format!("Weekly schedule of {} at {} (arrows to move, r to refresh, q to quit) {}",
+                              user_attrib.uname, now.format("%a %H:%M"), status);
+        // clear the screen and move the cursor home.
+        print!("\x1b[2J\x1b[H{}", dashboard.render(&heading, width, now, user_attrib.title_language, theme));
+        let _ = std::io::stdout().flush();
+        let key = match dashboard::read_key(&mut input) {
+            Ok(Some(key)) => key,
+            _ => break,
+        };
+        match dashboard.handle(key) {
+            Action::Redraw => {},
+            Action::Refresh => {
+                print!("\x1b[2J\x1b[HCrawling the list of {} again...", user_attrib.uname);
+                let _ = std::io::stdout().flush();
+                status = match requester::get_animelist_with(user_attrib, registered_trie, crawl_options) {
+                    Ok(anime_list) => {
+                        dashboard.update(args.query.apply(anime_list, local_now(args.utc_offset),
+                                                          user_attrib.title_language));
+                        String::new()
+                    },
+                    Err(err) => format!("- refresh failed: {}", err),
+                };
+            },
+            Action::Quit => break,
+        }
+    }
+    if let Some(saved) = saved {
+        stty(&[&saved]);
+    }
+    println!();
+    Ok(())
+}
+
+/// Keep the countdowns of the airing entries on screen, redrawn in place
+/// every minute from the crawled schedule, until the user quits.
+fn show_countdowns(uname: &str, anime_list: &[AnimeAttributes], language: TitleLanguage,
+                   utc_offset: Option<FixedOffset>, theme: &Theme) -> ! {
+    loop {
+        let now = local_now(utc_offset);
+        let mut airing: Vec<(&AnimeAttributes, Option<NaiveDateTime>)> = anime_list.iter()
+            .filter(|anime| !anime.is_finished())
+            .map(|anime| (anime, anime.next_airing(now)))
+            .collect();
+        // entries without a known airing time go last.
+        airing.sort_by_key(|(_, next)| (next.is_none(), *next));
+
+        // clear the screen and move the cursor home.
+        print!("\x1b[2J\x1b[H");
+        let heading = format!("Airing schedule of {} at {} (Ctrl-C to quit)", uname, now.format("%a %H:%M"));
+        println!("{}\n", theme::paint(theme.header, &heading));
+        for (anime, next) in airing {
+            let title = titles::pad_to_width(anime.preferred_title(language), 80);
+            let color = if anime.is_airing_today() { theme.airing } else { theme.title };
+            let when = match next {
+                Some(next) => format!("{} airs in {}", next.format("%a %H:%M"), schedule::format_countdown(next - now)),
+                None => anime.airing_weekday().map_or(String::new(), |day| day.to_string()),
+            };
+            println!("{} {}", theme::paint(color, &title), theme::paint(theme.time, &when));
+        }
+        let _ = std::io::stdout().flush();
+
+        let until_next_minute = 60 - now.second() as u64;
+        std::thread::sleep(std::time::Duration::from_secs(until_next_minute));
+    }
+}
@@ -0,0 +1,127 @@
+// Notifies a chat about the entries airing today after a crawl. The only
+// target so far is Discord: the entries are posted as an embed to a channel
+// webhook, whose url is the discord_webhook of config.toml.
+
+use std::str::FromStr;
+
+use serde_json::{json, Value};
+
+use crate::anime::AnimeAttributes;
+use crate::error::CrawlerError;
+use crate::http;
+use crate::titles::TitleLanguage;
+
+// Discord cuts embed descriptions longer than this.
+const MAX_DESCRIPTION_CHARS: usize = 4096;
+
+// Side color of the embed, MAL blue.
+const EMBED_COLOR: u32 = 0x2e51a2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notifier {
+    Discord,
+}
+
+impl FromStr for Notifier {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "discord" => Ok(Notifier::Discord),
+            other => Err(format!("unknown notification target {}, expected discord", other)),
+        }
+    }
+}
+
+/// the webhook message listing the entries of the user's list airing today,
+/// soonest first. None if no entry airs today.
+pub fn discord_message(uname: &str, entries: &[AnimeAttributes], language: TitleLanguage) -> Option<Value> {
+    let mut airing: Vec<&AnimeAttributes> = entries.iter()
+                                                   .filter(|anime| anime.is_airing && anime.is_airing_today())
+                                                   .collect();
+    if airing.is_empty() {
+        return None;
+    }
+    // entries without a known airing time go last.
+    airing.sort_by_key(|anime| (anime.airing_time.is_none(), anime.airing_time));
+
+    let mut description = String::new();
+    for (shown, anime) in airing.iter().enumerate() {
+        let mut line = format!("**{}**", anime.preferred_title(language));
+        if let Some(time) = anime.airing_time {
+            line.push_str(&time.format(" at %H:%M").to_string());
+        }
+        let more = format!("\n…and {} more", airing.len() - shown);
+        if description.chars().count() + line.chars().count() + 1 + more.chars().count() > MAX_DESCRIPTION_CHARS {
+            description.push_str(&more);
+            break;
+        }
+        if !description.is_empty() {
+            description.push('\n');
+        }
+        description.push_str(&line);
+    }
+    Some(json!({
+        "username": "anime-crawler",
+        "embeds": [{
+            "title": format!("Airing today on the list of {}", uname),
+            "description": description,
+            "color": EMBED_COLOR,
+        }],
+    }))
+}
+
+/// the webhook url without its token, which must not end up in messages.
+fn redacted(webhook: &str) -> String {
+    webhook.rsplit_once('/').map_or_else(|| String::from(webhook), |(base, _)| format!("{}/…", base))
+}
+
+/// post the message to the Discord webhook.
+pub fn post_discord(webhook: &str, message: &Value) -> Result<(), CrawlerError> {
+    let response = http::client().post(webhook)
+                                 .header(reqwest::header::CONTENT_TYPE, "application/json")
+                                 .body(message.to_string())
+                                 .send()
+                                 .map_err(|err| CrawlerError::request(&redacted(webhook), err.without_url()))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(CrawlerError::Status { url: redacted(webhook), status: status.as_u16(), retry_after: None });
+    }
+    Ok(())
+}
+
+
+#[test]
+fn test_discord_message() {
+    use chrono::{NaiveTime, Weekday};
+
+    let mut frieren = AnimeAttributes::new();
+    frieren.title = String::from("Sousou no Frieren");
+    frieren.is_airing = true;
+    frieren.set_airing_weekday(Weekday::Fri);
+    frieren.set_today(Weekday::Fri);
+    frieren.airing_time = NaiveTime::from_hms_opt(23, 0, 0);
+    let mut kaiju = frieren.clone();
+    kaiju.title = String::from("Kaijuu 8-gou");
+    kaiju.airing_time = None;
+    let mut mushishi = frieren.clone();
+    mushishi.title = String::from("Mushishi");
+    mushishi.is_airing = false;
+
+    let message = discord_message("goksgie", &[kaiju, frieren.clone(), mushishi.clone()], TitleLanguage::Romaji).unwrap();
+    let embed = &message["embeds"][0];
+    assert_eq!(embed["title"], "Airing today on the list of goksgie");
+    assert_eq!(embed["description"], "**Sousou no Frieren** at 23:00\n**Kaijuu 8-gou**");
+    assert_eq!(discord_message("goksgie", &[mushishi], TitleLanguage::Romaji), None);
+
+    // long lists are cut to what Discord shows.
+    let many = vec![frieren; 400];
+    let message = discord_message("goksgie", &many, TitleLanguage::Romaji).unwrap();
+    let description = message["embeds"][0]["description"].as_str().unwrap();
+    assert!(description.chars().count() <= MAX_DESCRIPTION_CHARS);
+    assert!(description.ends_with("more"));
+
+    assert_eq!(redacted("https://discord.com/api/webhooks/123/secret"), "https://discord.com/api/webhooks/123/…");
+    assert_eq!("discord".parse::<Notifier>(), Ok(Notifier::Discord));
+    assert!("slack".parse::<Notifier>().is_err());
+}
@@ -0,0 +1,124 @@
+// Writes crawled entries to files other tools can read: a JSON array of
+// objects, or CSV with a header row. Both carry the same fields, airing
+// day and time in the local timezone.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::anime::AnimeAttributes;
+use crate::error::CrawlerError;
+
+const CSV_HEADER: &str = "id,title,title_eng,title_jp,status,score,num_watched_episodes,num_episodes,\
+                          start_date,is_airing,airing_today,airing_weekday,airing_time,\
+                          genres,studios,source,mean_score";
+
+#[derive(Serialize)]
+struct Row<'a> {
+    id: i32,
+    title: &'a str,
+    title_eng: &'a str,
+    title_jp: &'a str,
+    status: i32,
+    score: i32,
+    num_watched_episodes: i32,
+    num_episodes: i32,
+    start_date: &'a str,
+    is_airing: bool,
+    airing_today: bool,
+    airing_weekday: Option<String>,
+    airing_time: Option<String>,
+    genres: &'a [String],
+    studios: &'a [String],
+    source: Option<&'a str>,
+    mean_score: Option<f32>,
+}
+
+impl<'a> Row<'a> {
+    fn new(anime: &'a AnimeAttributes) -> Self {
+        Row { id: anime.id, title: &anime.title, title_eng: &anime.title_eng, title_jp: &anime.title_jp,
+              status: anime.status, score: anime.score, num_watched_episodes: anime.num_watched_episodes,
+              num_episodes: anime.num_episodes, start_date: &anime.start_date,
+              is_airing: anime.is_airing, airing_today: anime.is_airing_today(),
+              airing_weekday: anime.airing_weekday().map(|day| day.to_string()),
+              airing_time: anime.airing_time.map(|time| time.format("%H:%M").to_string()),
+              genres: &anime.genres, studios: &anime.studios, source: anime.source.as_deref(),
+              mean_score: anime.mean_score }
+    }
+}
+
+/// quote a CSV field if it contains a separator, a quote or a line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+pub fn to_json(entries: &[AnimeAttributes]) -> String {
+    let rows: Vec<Row> = entries.iter().map(Row::new).collect();
+    // rows are plain strings and numbers, which always serialize.
+    serde_json::to_string_pretty(&rows).unwrap_or_default()
+}
+
+pub fn to_csv(entries: &[AnimeAttributes]) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for row in entries.iter().map(Row::new) {
+        let fields = [row.id.to_string(), csv_field(row.title), csv_field(row.title_eng),
+                      csv_field(row.title_jp), row.status.to_string(), row.score.to_string(),
+                      row.num_watched_episodes.to_string(), row.num_episodes.to_string(),
+                      csv_field(row.start_date), row.is_airing.to_string(), row.airing_today.to_string(),
+                      row.airing_weekday.unwrap_or_default(), row.airing_time.unwrap_or_default(),
+                      csv_field(&row.genres.join(", ")), csv_field(&row.studios.join(", ")),
+                      csv_field(row.source.unwrap_or_default()),
+                      row.mean_score.map_or(String::new(), |score| score.to_string())];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn write(path: &Path, contents: String) -> Result<(), CrawlerError> {
+    fs::write(path, contents).map_err(|err| {
+        CrawlerError::io(format!("could not write {}", path.display()), err)
+    })
+}
+
+pub fn write_json(path: &Path, entries: &[AnimeAttributes]) -> Result<(), CrawlerError> {
+    write(path, to_json(entries))
+}
+
+pub fn write_csv(path: &Path, entries: &[AnimeAttributes]) -> Result<(), CrawlerError> {
+    write(path, to_csv(entries))
+}
+
+/// write the entries as CSV if the path ends in .csv, as JSON otherwise.
+pub fn write_file(path: &Path, entries: &[AnimeAttributes]) -> Result<(), CrawlerError> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("csv") => write_csv(path, entries),
+        _ => write_json(path, entries),
+    }
+}
+
+
+#[test]
+fn test_output_formats() {
+    let mut anime = AnimeAttributes::new();
+    anime.id = 52991;
+    anime.title = String::from("Sousou no Frieren");
+    anime.title_eng = String::from("Frieren: Beyond Journey's End, \"Season 1\"");
+    anime.num_episodes = 28;
+
+    let json: serde_json::Value = serde_json::from_str(&to_json(&[anime.clone()])).unwrap();
+    assert_eq!(json[0]["id"], 52991);
+    assert_eq!(json[0]["title_eng"], "Frieren: Beyond Journey's End, \"Season 1\"");
+    assert_eq!(json[0]["airing_weekday"], serde_json::Value::Null);
+
+    let csv = to_csv(&[anime]);
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some(CSV_HEADER));
+    assert!(lines.next().unwrap().starts_with(
+        "52991,Sousou no Frieren,\"Frieren: Beyond Journey's End, \"\"Season 1\"\"\",,0,0,0,28,"));
+}
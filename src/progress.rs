@@ -0,0 +1,95 @@
+// Progress of a crawl as it happens. Crawling a long list fetches an anime
+// page per entry, which takes minutes, so a reporter hears about the parsed
+// list, every fetched page and every retried request. ProgressBar draws
+// them as a bar on a terminal.
+
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Callbacks invoked by the requester while crawling a list. Every method
+/// has a default implementation, so a reporter only overrides what it needs.
+pub trait ProgressReporter: Send + Sync {
+    /// called once the list is parsed, with its number of entries and how
+    /// many of their anime pages are fetched.
+    fn on_list_parsed(&self, _entries: usize, _pages: usize) {}
+
+    /// called after every anime page, `done` of `total` are fetched.
+    fn on_page_fetched(&self, _done: usize, _total: usize) {}
+
+    /// called before a request MAL turned away is sent again.
+    fn on_retry(&self, _url: &str, _attempt: u32) {}
+}
+
+const BAR_WIDTH: usize = 30;
+
+/// the bar of `done` of `total` steps, e.g. "[#######        ] 12/40".
+pub fn render_bar(done: usize, total: usize, width: usize) -> String {
+    let filled = (width * done.min(total)).checked_div(total).unwrap_or(width);
+    format!("[{}{}] {}/{}", "#".repeat(filled), " ".repeat(width - filled), done, total)
+}
+
+#[derive(Debug, Default)]
+struct BarState {
+    entries: usize,
+    retries: usize,
+}
+
+/// Draws the progress of the anime page fetches on stderr, redrawn in place
+/// on a single line that is ended once every page is fetched.
+#[derive(Debug, Default)]
+pub struct ProgressBar {
+    state: Mutex<BarState>,
+}
+
+impl ProgressBar {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn draw(&self, done: usize, total: usize) {
+        let (entries, retries) = match self.state.lock() {
+            Ok(state) => (state.entries, state.retries),
+            Err(_) => return,
+        };
+        let mut line = format!("\r{} anime pages of {} entries", render_bar(done, total, BAR_WIDTH), entries);
+        if retries > 0 {
+            line.push_str(&format!(", {} retried", retries));
+        }
+        if done >= total {
+            line.push('\n');
+        }
+        let mut stderr = std::io::stderr();
+        let _ = stderr.write_all(line.as_bytes()).and_then(|_| stderr.flush());
+    }
+}
+
+impl ProgressReporter for ProgressBar {
+    fn on_list_parsed(&self, entries: usize, pages: usize) {
+        if let Ok(mut state) = self.state.lock() {
+            *state = BarState { entries, retries: 0 };
+        }
+        // lists without pages to fetch are done right away.
+        if pages > 0 {
+            self.draw(0, pages);
+        }
+    }
+
+    fn on_page_fetched(&self, done: usize, total: usize) {
+        self.draw(done, total);
+    }
+
+    fn on_retry(&self, _url: &str, _attempt: u32) {
+        if let Ok(mut state) = self.state.lock() {
+            state.retries += 1;
+        }
+    }
+}
+
+
+#[test]
+fn test_render_bar() {
+    assert_eq!(render_bar(0, 4, 8), "[        ] 0/4");
+    assert_eq!(render_bar(3, 4, 8), "[######  ] 3/4");
+    assert_eq!(render_bar(5, 4, 8), "[########] 5/4");
+    assert_eq!(render_bar(0, 0, 4), "[####] 0/0");
+}
@@ -0,0 +1,52 @@
+// Python bindings, so the crawler can be called from notebooks without
+// shelling out to the binary. Build with `maturin develop --features python`.
+
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::types::PyDict;
+
+use crate::anime::{AnimeAttributes, UserAttributes};
+use crate::config::REGISTERED_WORDS;
+use crate::requester;
+use crate::trie::Trie;
+
+fn anime_to_dict<'py>(py: Python<'py>, anime: &AnimeAttributes) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("id", anime.id)?;
+    dict.set_item("title", &anime.title)?;
+    dict.set_item("title_eng", &anime.title_eng)?;
+    dict.set_item("title_jp", &anime.title_jp)?;
+    dict.set_item("status", anime.status)?;
+    dict.set_item("score", anime.score)?;
+    dict.set_item("num_episodes", anime.num_episodes)?;
+    dict.set_item("start_date", &*anime.start_date)?;
+    dict.set_item("is_airing", anime.is_airing)?;
+    dict.set_item("airing_today", anime.is_airing_today())?;
+    Ok(dict)
+}
+
+/// Crawls the watching list of `uname` and returns one dict per entry.
+/// `date_format` is 1 for day-month-year and 2 for month-day-year.
+#[pyfunction]
+fn get_animelist<'py>(py: Python<'py>, uname: String,
+                      date_format: i32) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    if !(1..=2).contains(&date_format) {
+        return Err(PyValueError::new_err(format!("date_format must be 1 or 2, got {}", date_format)));
+    }
+
+    let anime_list = py.allow_threads(|| {
+        let registered_words = REGISTERED_WORDS.to_vec();
+        let registered_trie = Trie::new(Some(&registered_words));
+        let mut user_attrib = UserAttributes::new(uname);
+        user_attrib.set_date_format(date_format.to_string())?;
+        requester::get_animelist(&user_attrib, &registered_trie)
+    }).map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+
+    anime_list.iter().map(|anime| anime_to_dict(py, anime)).collect()
+}
+
+#[pymodule]
+fn anime_crawler(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(get_animelist, m)?)?;
+    Ok(())
+}
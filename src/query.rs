@@ -0,0 +1,219 @@
+// Filters and orders a crawled list before it is shown or written, e.g. only
+// the entries airing today with a community score of at least 7, soonest
+// first. A Query is a set of predicates every kept entry satisfies, plus an
+// optional sort key.
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDateTime};
+
+use crate::anime::AnimeAttributes;
+use crate::titles::{self, TitleLanguage};
+
+/// A predicate on the entries of a list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    AiringToday,
+    /// community score of at least this, entries without one are dropped.
+    MinScore(f32),
+    /// one of the genres of the entry, ignoring case.
+    Genre(String),
+    /// one of the titles of the entry matches the text loosely, see
+    /// titles::fuzzy_score.
+    Search(String),
+}
+
+impl Filter {
+    pub fn matches(&self, anime: &AnimeAttributes) -> bool {
+        match self {
+            Filter::AiringToday => anime.is_airing && anime.is_airing_today(),
+            Filter::MinScore(min) => anime.mean_score.is_some_and(|score| score >= *min),
+            Filter::Genre(genre) => anime.genres.iter().any(|name| name.eq_ignore_ascii_case(genre)),
+            Filter::Search(text) => titles::search_score(anime, text).is_some(),
+        }
+    }
+
+    /// true if the filter reads what only the anime pages or the API report.
+    pub fn needs_metadata(&self) -> bool {
+        matches!(self, Filter::MinScore(_) | Filter::Genre(_))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// soonest next episode first, entries that are not airing last.
+    AiringTime,
+    Title,
+    /// highest community score first, unscored entries last.
+    Score,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "airing-time" => Ok(SortKey::AiringTime),
+            "title" => Ok(SortKey::Title),
+            "score" => Ok(SortKey::Score),
+            other => Err(format!("unknown sort key {}, expected airing-time, title or score", other)),
+        }
+    }
+}
+
+/// minutes from `now` until the next episode, None if the entry is not
+/// airing. Without a known airing time, episodes count as airing at midnight.
+fn minutes_until_airing(anime: &AnimeAttributes, now: NaiveDateTime) -> Option<i64> {
+    if let Some(next) = anime.next_airing(now) {
+        return Some((next - now).num_minutes());
+    }
+    let weekday = anime.airing_weekday()?;
+    let days_ahead = (weekday.num_days_from_monday() as i64
+                      - now.weekday().num_days_from_monday() as i64).rem_euclid(7);
+    Some(days_ahead * 24 * 60)
+}
+
+/// None last, whatever the order of the values.
+fn cmp_known<T: PartialOrd>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let order = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+            if descending { order.reverse() } else { order }
+        },
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    filters: Vec<Filter>,
+    sort: Option<SortKey>,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// keep only the entries the filter matches, on top of the other filters.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    pub fn sort_by(mut self, key: SortKey) -> Self {
+        self.sort = Some(key);
+        self
+    }
+
+    /// true if the query keeps the list as it is.
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty() && self.sort.is_none()
+    }
+
+    /// true if the crawl has to read the metadata of the entries for it.
+    pub fn needs_metadata(&self) -> bool {
+        self.filters.iter().any(Filter::needs_metadata) || self.sort == Some(SortKey::Score)
+    }
+
+    pub fn matches(&self, anime: &AnimeAttributes) -> bool {
+        self.filters.iter().all(|filter| filter.matches(anime))
+    }
+
+    /// the matching entries in the order of the sort key. Without one,
+    /// searches order them best match first, otherwise the order of the
+    /// list is kept. `now` is the local time airing times are
+    /// counted from and `language` the one titles are sorted in.
+    pub fn apply(&self, anime_list: Vec<AnimeAttributes>, now: NaiveDateTime,
+                 language: TitleLanguage) -> Vec<AnimeAttributes> {
+        let mut entries: Vec<AnimeAttributes> = anime_list.into_iter().filter(|anime| self.matches(anime)).collect();
+        match self.sort {
+            Some(SortKey::AiringTime) => {
+                entries.sort_by_key(|anime| {
+                    let minutes = minutes_until_airing(anime, now);
+                    (minutes.is_none(), minutes)
+                });
+            },
+            Some(SortKey::Title) => {
+                entries.sort_by_cached_key(|anime| anime.preferred_title(language).to_lowercase());
+            },
+            Some(SortKey::Score) => entries.sort_by(|a, b| cmp_known(a.mean_score, b.mean_score, true)),
+            None => {
+                let searches: Vec<&str> = self.filters.iter().filter_map(|filter| match filter {
+                    Filter::Search(text) => Some(text.as_str()),
+                    _ => None,
+                }).collect();
+                if !searches.is_empty() {
+                    entries.sort_by_cached_key(|anime| {
+                        let score: u32 = searches.iter().filter_map(|text| titles::search_score(anime, text)).sum();
+                        std::cmp::Reverse(score)
+                    });
+                }
+            },
+        }
+        entries
+    }
+}
+
+
+#[test]
+fn test_query() {
+    use chrono::{NaiveDate, NaiveTime, Weekday};
+
+    // a thursday.
+    let now = NaiveDate::from_ymd_opt(2023, 10, 5).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    let mut frieren = AnimeAttributes::new();
+    frieren.title = String::from("Sousou no Frieren");
+    frieren.is_airing = true;
+    frieren.set_airing_weekday(Weekday::Fri);
+    frieren.airing_time = NaiveTime::from_hms_opt(23, 0, 0);
+    frieren.genres = vec![String::from("Adventure"), String::from("Fantasy")];
+    frieren.mean_score = Some(9.1);
+    let mut mushishi = AnimeAttributes::new();
+    mushishi.title = String::from("mushishi");
+    mushishi.genres = vec![String::from("Mystery")];
+    mushishi.mean_score = Some(8.7);
+    let mut kaiju = AnimeAttributes::new();
+    kaiju.title = String::from("Kaijuu 8-gou");
+    kaiju.is_airing = true;
+    kaiju.set_airing_weekday(Weekday::Thu);
+    let list = vec![frieren, mushishi, kaiju];
+    let titles = |entries: Vec<AnimeAttributes>| -> Vec<String> {
+        entries.into_iter().map(|anime| anime.title).collect()
+    };
+
+    assert!(Query::new().is_empty());
+    assert_eq!(titles(Query::new().apply(list.clone(), now, TitleLanguage::Romaji)),
+               vec!["Sousou no Frieren", "mushishi", "Kaijuu 8-gou"]);
+
+    let query = Query::new().filter(Filter::MinScore(8.0)).filter(Filter::Genre(String::from("fantasy")));
+    assert!(query.needs_metadata());
+    assert_eq!(titles(query.apply(list.clone(), now, TitleLanguage::Romaji)), vec!["Sousou no Frieren"]);
+
+    let by_score = Query::new().sort_by(SortKey::Score);
+    assert_eq!(titles(by_score.apply(list.clone(), now, TitleLanguage::Romaji)),
+               vec!["Sousou no Frieren", "mushishi", "Kaijuu 8-gou"]);
+    let by_title = Query::new().sort_by("title".parse().unwrap());
+    assert_eq!(titles(by_title.apply(list.clone(), now, TitleLanguage::Romaji)),
+               vec!["Kaijuu 8-gou", "mushishi", "Sousou no Frieren"]);
+    // airing today without a known time, then tomorrow, then not airing.
+    let by_airing = Query::new().sort_by(SortKey::AiringTime);
+    assert!(!by_airing.needs_metadata());
+    assert_eq!(titles(by_airing.apply(list, now, TitleLanguage::Romaji)),
+               vec!["Kaijuu 8-gou", "Sousou no Frieren", "mushishi"]);
+    assert!("episodes".parse::<SortKey>().is_err());
+
+    let mut spy = AnimeAttributes::new();
+    spy.title = String::from("Spy x Family");
+    let mut fma = AnimeAttributes::new();
+    fma.title = String::from("Fullmetal Alchemist");
+    let mut mushishi = AnimeAttributes::new();
+    mushishi.title = String::from("Mushishi");
+    let search = Query::new().filter(Filter::Search(String::from("fam")));
+    assert!(!search.needs_metadata());
+    assert_eq!(titles(search.apply(vec![fma, mushishi, spy], now, TitleLanguage::Romaji)),
+               vec!["Spy x Family", "Fullmetal Alchemist"]);
+}
@@ -0,0 +1,164 @@
+// Spaces out requests to MAL, so that the worker pool does not hammer the
+// site. The limiter is shared by every thread of a crawl. HostLimiter caps
+// how many requests are in flight per host at once, independently of the
+// number of workers.
+
+use std::collections::HashMap;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::CrawlerError;
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// allow at most one request per `interval`.
+    pub fn new(interval: Duration) -> Self {
+        RateLimiter { interval, next_slot: Mutex::new(Instant::now()) }
+    }
+
+    /// allow at most `rate` requests per second. Fails if the rate is not
+    /// positive or so small that its interval does not fit a Duration.
+    pub fn per_second(rate: f64) -> Result<Self, CrawlerError> {
+        Duration::try_from_secs_f64(1.0 / rate).ok().filter(|_| rate > 0.0)
+                                               .map(RateLimiter::new)
+                                               .ok_or(CrawlerError::InvalidRate(rate))
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// reserve the next slot and return how long the caller has to wait
+    /// for it, for callers that cannot block their thread. Slots are
+    /// reserved in call order, so concurrent callers do not wake up together.
+    pub fn reserve(&self) -> Duration {
+        let slot = match self.next_slot.lock() {
+            Ok(mut next_slot) => {
+                let slot = std::cmp::max(*next_slot, Instant::now());
+                *next_slot = slot + self.interval;
+                slot
+            },
+            Err(_) => return Duration::ZERO,
+        };
+        slot.saturating_duration_since(Instant::now())
+    }
+
+    /// block until the caller is allowed to send its request.
+    pub fn wait(&self) {
+        thread::sleep(self.reserve());
+    }
+}
+
+/// host of the url, e.g. "myanimelist.net".
+fn host_of(url: &str) -> &str {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = without_scheme.split(['/', '?', '#']).next().unwrap_or(without_scheme);
+    authority.rsplit('@').next().unwrap_or(authority)
+}
+
+#[derive(Debug)]
+pub struct HostLimiter {
+    max_per_host: usize,
+    active: Mutex<HashMap<String, usize>>,
+    released: Condvar,
+}
+
+/// a request slot of a host, given back when dropped.
+#[derive(Debug)]
+pub struct HostPermit<'a> {
+    limiter: &'a HostLimiter,
+    host: String,
+}
+
+impl HostLimiter {
+    /// allow at most `max_per_host` concurrent requests to each host, at
+    /// least one.
+    pub fn new(max_per_host: usize) -> Self {
+        HostLimiter { max_per_host: max_per_host.max(1), active: Mutex::new(HashMap::new()),
+                      released: Condvar::new() }
+    }
+
+    pub fn max_per_host(&self) -> usize {
+        self.max_per_host
+    }
+
+    /// block until a request to the host of the url may be sent.
+    pub fn acquire(&self, url: &str) -> HostPermit<'_> {
+        let host = String::from(host_of(url));
+        let mut active = match self.active.lock() {
+            Ok(active) => active,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        while active.get(&host).copied().unwrap_or(0) >= self.max_per_host {
+            active = match self.released.wait(active) {
+                Ok(active) => active,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+        *active.entry(host.clone()).or_insert(0) += 1;
+        HostPermit { limiter: self, host }
+    }
+
+    /// number of requests in flight to the given host.
+    pub fn in_flight(&self, host: &str) -> usize {
+        self.active.lock().map_or(0, |active| active.get(host).copied().unwrap_or(0))
+    }
+}
+
+impl Drop for HostPermit<'_> {
+    fn drop(&mut self) {
+        let mut active = match self.limiter.active.lock() {
+            Ok(active) => active,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Some(count) = active.get_mut(&self.host) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(&self.host);
+            }
+        }
+        self.limiter.released.notify_all();
+    }
+}
+
+
+#[test]
+fn test_rate_limiter_spaces_requests() {
+    assert_eq!(RateLimiter::per_second(4.0).unwrap().interval(), Duration::from_millis(250));
+    assert!(RateLimiter::per_second(1e-300).is_err());
+    assert!(RateLimiter::per_second(0.0).is_err());
+    let limiter = RateLimiter::new(Duration::from_millis(20));
+    let started = Instant::now();
+    for _ in 0..3 {
+        limiter.wait();
+    }
+    assert!(started.elapsed() >= Duration::from_millis(40));
+}
+
+#[test]
+fn test_host_limiter_caps_each_host() {
+    let limiter = HostLimiter::new(1);
+    let mal = limiter.acquire("https://myanimelist.net/anime/5081/");
+    // other hosts are not held up by myanimelist.net.
+    let jikan = limiter.acquire("https://api.jikan.moe/v4/anime/5081");
+    assert_eq!(limiter.in_flight("myanimelist.net"), 1);
+    assert_eq!(limiter.in_flight("api.jikan.moe"), 1);
+
+    thread::scope(|scope| {
+        let waiter = scope.spawn(|| {
+            let _permit = limiter.acquire("https://myanimelist.net/anime/457/");
+        });
+        thread::sleep(Duration::from_millis(20));
+        assert!(!waiter.is_finished());
+        drop(mal);
+        waiter.join().unwrap();
+    });
+    drop(jikan);
+    assert_eq!(limiter.in_flight("myanimelist.net"), 0);
+}
@@ -0,0 +1,47 @@
+// Diagnostics collected while parsing a list page, so users can tell when
+// MAL added fields the crawler does not handle yet, or when entries were
+// dropped because they could not be parsed.
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedEntry {
+    /// position of the entry in the list payload, starting at 0.
+    pub position: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseReport {
+    /// keys of the payload that are not registered, with the number of
+    /// entries they appeared in.
+    pub unrecognized_keys: BTreeMap<String, usize>,
+    pub skipped_entries: Vec<SkippedEntry>,
+    /// why the list payload could not be decoded, if it could not.
+    pub invalid_payload: Option<String>,
+    /// the parsing limit that cut the list short, if any.
+    pub limit_reached: Option<String>,
+    /// why the entries past the first pages of a long list are missing, if
+    /// a later page could not be loaded.
+    pub incomplete: Option<String>,
+}
+
+impl ParseReport {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn unrecognized_key(&mut self, key: &str) {
+        *self.unrecognized_keys.entry(String::from(key)).or_insert(0) += 1;
+    }
+
+    pub fn skipped_entry(&mut self, position: usize, reason: String) {
+        self.skipped_entries.push(SkippedEntry { position, reason });
+    }
+
+    /// true if the parser handled everything it came across.
+    pub fn is_clean(&self) -> bool {
+        self.unrecognized_keys.is_empty() && self.skipped_entries.is_empty()
+            && self.limit_reached.is_none() && self.invalid_payload.is_none() && self.incomplete.is_none()
+    }
+}
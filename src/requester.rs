@@ -1,174 +1,1487 @@
 // write a parser here, and also get the request functions in this
 // module. So that we can call them from trie.
 
-use std::sync::{Mutex, Arc};
+pub mod api;
+#[cfg(feature = "async")]
+pub mod async_client;
+mod classic;
+pub mod page;
+pub mod profile;
+pub mod retry;
+pub mod robots;
+pub mod season;
+
+pub use self::page::{MarkupParser, PageParser};
+pub use self::profile::{get_user_profile, UserProfile};
+pub use self::retry::RetryPolicy;
+pub use self::season::{get_seasonal, Season, SeasonalAnime};
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{mpsc, Mutex, Arc, OnceLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
+use std::time::Instant;
+use chrono::{FixedOffset, NaiveTime, Timelike, Utc, Weekday};
+use serde_json::Value;
+use tracing::{debug, info, info_span, instrument, warn};
 use crate::trie::Trie;
-use crate::anime::{AnimeAttributes, UserAttributes};
-use crate::config::{TIME_DIFF_TO_JST, HOUR_IDENTIFIER};
+use crate::titles::TitleLanguage;
+use crate::hooks::HookRegistry;
+use crate::progress::ProgressReporter;
+use crate::cancel::CancellationToken;
+use crate::checkpoint::Checkpoint;
+use crate::audit;
+use crate::broadcast_cache::BroadcastCache;
+use crate::transport::Transport;
+use crate::rate_limit::{HostLimiter, RateLimiter};
+use self::api::ApiAuth;
+use self::robots::Robots;
+use crate::error::CrawlerError;
+use crate::report::ParseReport;
+use crate::anime::{infer_date_format, AnimeAttributes, BroadcastSchedule, UserAttributes};
+use crate::config::{self, HOUR_IDENTIFIER, IGNORED_LIST_KEYS, LIST_RESTRICTED_NOTICE, MAL_URL, NUM_WORKERS, MAX_REQUESTS_PER_HOST,
+                    MAX_PAGE_BYTES, MAX_LIST_ENTRIES, LIST_PAGE_SIZE};
 
-extern crate reqwest;
 
-/// Fetches precise airing date of the anime and updates
-/// the airing day of it
-fn update_precise_airing_day(anime: &mut AnimeAttributes) {
-    let anime_id = anime.id; 
-    let url = format!("https://myanimelist.net/anime/{}/", anime_id);
-    let res = reqwest::blocking::get(url);
+/// Settings of a single crawl.
+#[derive(Default)]
+pub struct CrawlOptions {
+    /// hooks invoked while crawling.
+    pub hooks: HookRegistry,
+    /// hears about the progress of the crawl.
+    pub progress: Option<Arc<dyn ProgressReporter>>,
+    /// finds the fields of anime pages, MarkupParser if unset.
+    pub parser: Option<Arc<dyn PageParser>>,
+    /// aborts the crawl when cancelled from another thread.
+    pub cancel: CancellationToken,
+    /// when the crawl is cancelled while fetching anime pages, return the
+    /// entries instead of failing with CrawlerError::Cancelled. Entries whose
+    /// page was not fetched keep the airing day read from the list, and the
+    /// report of the crawl tells it is incomplete.
+    pub partial_results: bool,
+    /// keep a checkpoint of fetched anime pages, so that an interrupted
+    /// crawl of the same user resumes where it left off.
+    pub resume: bool,
+    /// anime pages shared with other crawls of this run.
+    pub page_cache: Option<Arc<AnimePageCache>>,
+    /// where pages are fetched from: the network, or recorded fixtures.
+    pub transport: Transport,
+    /// scheme and host of the pages, MAL_URL if unset, e.g. a local server
+    /// serving fixtures in tests. The API is always asked at API_URL.
+    pub base_url: Option<String>,
+    /// spaces out the requests of the crawl.
+    pub rate_limit: Option<Arc<RateLimiter>>,
+    /// retries of requests MAL rate limited or could not serve.
+    pub retry: RetryPolicy,
+    /// number of workers fetching anime pages, NUM_WORKERS if unset.
+    pub workers: Option<usize>,
+    /// caps the requests in flight per host, MAX_REQUESTS_PER_HOST if unset.
+    pub host_limit: Option<Arc<HostLimiter>>,
+    /// hours between the local time and JST, detected from the host's
+    /// timezone if unset. Wins over utc_offset.
+    pub time_diff_to_jst: Option<i32>,
+    /// offset of the local time from UTC, for timezones whose offset is
+    /// not a whole number of hours. Detected from the host if unset.
+    pub utc_offset: Option<FixedOffset>,
+    /// crawl paths disallowed by robots.txt and ignore its crawl delay.
+    pub ignore_robots: bool,
+    /// broadcast hours persisted between runs.
+    pub broadcast_cache: Option<Arc<BroadcastCache>>,
+    /// fetch the page of every airing entry, so that all of them get their
+    /// broadcast time, not only those whose airing day is ambiguous.
+    pub broadcast_times: bool,
+    /// fetch the page of every entry for its Japanese title.
+    pub japanese_titles: bool,
+    /// fetch the page of every entry for its genres, studios, source
+    /// material and community score.
+    pub metadata: bool,
+    /// read lists from the official MAL API instead of scraping the list
+    /// page. The list page is scraped if the API request fails.
+    pub api: Option<ApiAuth>,
+}
 
-    let body = if res.is_ok() {
-        res.unwrap().text().unwrap_or(String::new())
-    } else {
-        String::new()
-    };
+/// Entries of a crawled list, together with the diagnostics of parsing it.
+#[derive(Debug, Default)]
+pub struct CrawlResult {
+    pub entries: Vec<AnimeAttributes>,
+    pub report: ParseReport,
+}
 
-    // we are only interested in the airing hour of the anime, so that we
-    // can deduce the airing day correctly. Hence, I do not provide a proper
-    // html parser. Despite that, the operations performed here are too
-    // expensive. TODO: Improve HTML PARSER. 
-    let tokenized_body: Vec<&str> = body.split('\n').collect();
-    let mut index = 0;
-    let mut shifting = 0;
-
-    while index < tokenized_body.len() {
-        let line = tokenized_body[index];
-        let length_line = line.len();
-        if length_line  >= 44 && length_line < 48 && line[28..].contains(HOUR_IDENTIFIER) {
-            // we have found the Broadcast date information.
-            let date_tokenized: Vec<&str> = tokenized_body[index + 1]
-                                                    .trim().split(' ').collect();
-            let hour_min_tokenized: Vec<&str> = date_tokenized[2].split(':').collect();
-            let hour = hour_min_tokenized[0].parse::<i32>().unwrap();
-            let min  = hour_min_tokenized[1].parse::<i32>().unwrap();
-
-            if TIME_DIFF_TO_JST < 0  && hour + TIME_DIFF_TO_JST  >= 24 {
-                shifting = 1; 
-            } else if TIME_DIFF_TO_JST > 0 && hour - TIME_DIFF_TO_JST < 0 {
-                shifting = -1;
+/// What a crawl reads from an anime page.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct AnimePage {
+    /// broadcast time in JST, None if the page has none.
+    broadcast: Option<NaiveTime>,
+    /// broadcast weekday in JST, not kept by the broadcast cache.
+    weekday: Option<Weekday>,
+    title_jp: Option<String>,
+    /// not kept by the broadcast cache either.
+    metadata: Option<Metadata>,
+}
+
+/// What an anime page tells about the anime beyond its broadcast.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Metadata {
+    genres: Vec<String>,
+    studios: Vec<String>,
+    source: Option<String>,
+    score: Option<f32>,
+    /// seconds per episode.
+    duration: Option<u32>,
+}
+
+/// None if the anime page could not be fetched.
+type FetchedPage = Option<AnimePage>;
+
+/// Anime page results shared by the crawls of one run, so that the page of
+/// an anime on several lists is fetched at most once, even when the crawls
+/// run concurrently. Counts how often a crawl found the page it asked for
+/// already there.
+#[derive(Debug, Default)]
+pub struct AnimePageCache {
+    pages: Mutex<HashMap<i32, Arc<OnceLock<FetchedPage>>>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl AnimePageCache {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// page of the given anime. The first caller fetches the page,
+    /// concurrent callers for the same anime wait for that fetch.
+    fn page(&self, sources: &PageSources, anime_id: i32) -> FetchedPage {
+        let page = match self.pages.lock() {
+            Ok(mut pages) => Arc::clone(pages.entry(anime_id).or_default()),
+            Err(_) => return fetch_page(sources, anime_id),
+        };
+        let mut fetched = false;
+        let page = page.get_or_init(|| {
+            fetched = true;
+            fetch_page(sources, anime_id)
+        }).clone();
+        self.count(!fetched);
+        page
+    }
+
+    /// page of the given anime if a crawl already fetched it, for crawls
+    /// that fetch pages themselves and store them with `insert`.
+    #[cfg(feature = "async")]
+    fn get(&self, anime_id: i32) -> Option<FetchedPage> {
+        let page = self.pages.lock().ok()?.get(&anime_id).and_then(|page| page.get().cloned());
+        self.count(page.is_some());
+        page
+    }
+
+    #[cfg(feature = "async")]
+    fn insert(&self, anime_id: i32, page: FetchedPage) {
+        if let Ok(mut pages) = self.pages.lock() {
+            let _ = pages.entry(anime_id).or_default().set(page);
+        }
+    }
+
+    fn count(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// number of distinct anime pages requested so far.
+    pub fn len(&self) -> usize {
+        self.pages.lock().map(|pages| pages.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// requests for a page that was already there.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// requests for a page that had to be fetched.
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+/// Where the workers look for the day shift of an anime before fetching it.
+#[derive(Clone)]
+struct PageSources {
+    transport: Transport,
+    /// scheme and host of the pages, without a trailing slash.
+    base_url: String,
+    checkpoint: Option<Arc<Checkpoint>>,
+    page_cache: Option<Arc<AnimePageCache>>,
+    rate_limit: Option<Arc<RateLimiter>>,
+    retry: RetryPolicy,
+    progress: Option<Arc<dyn ProgressReporter>>,
+    parser: Arc<dyn PageParser>,
+    workers: usize,
+    host_limit: Arc<HostLimiter>,
+    robots: Option<Arc<Robots>>,
+    broadcast_cache: Option<Arc<BroadcastCache>>,
+    broadcast_times: bool,
+    japanese_titles: bool,
+    metadata: bool,
+    minutes_to_jst: i32,
+}
+
+/// minutes between the local time and JST the options ask for.
+fn minutes_to_jst(options: &CrawlOptions) -> i32 {
+    match (options.time_diff_to_jst, options.utc_offset) {
+        (Some(hours), _) => hours * 60,
+        (None, Some(offset)) => config::minutes_to_jst_from(offset),
+        (None, None) => config::minutes_to_jst(),
+    }
+}
+
+impl PageSources {
+    fn new(options: &CrawlOptions) -> Self {
+        let base_url = options.base_url.as_deref().unwrap_or(MAL_URL).trim_end_matches('/');
+        PageSources { transport: options.transport.clone(), base_url: String::from(base_url), checkpoint: None,
+                      page_cache: options.page_cache.clone(),
+                      rate_limit: options.rate_limit.clone(),
+                      retry: options.retry.clone(),
+                      progress: options.progress.clone(),
+                      parser: options.parser.clone().unwrap_or_else(|| Arc::new(MarkupParser)),
+                      workers: options.workers.unwrap_or(NUM_WORKERS).max(1),
+                      host_limit: options.host_limit.clone().unwrap_or_else(|| {
+                          Arc::new(HostLimiter::new(MAX_REQUESTS_PER_HOST))
+                      }),
+                      robots: None,
+                      broadcast_cache: options.broadcast_cache.clone(),
+                      broadcast_times: options.broadcast_times,
+                      japanese_titles: options.japanese_titles,
+                      metadata: options.metadata,
+                      minutes_to_jst: minutes_to_jst(options) }
+    }
+
+    /// same as new, but unless the options ignore it, fetches robots.txt
+    /// and slows the requests down to its crawl delay.
+    fn for_crawl(options: &CrawlOptions) -> Self {
+        let mut sources = PageSources::new(options);
+        if !options.ignore_robots {
+            sources.honor_robots(Robots::fetch(&options.transport, &sources.base_url));
+        }
+        sources
+    }
+
+    /// keep away from paths robots.txt disallows and slow the requests down
+    /// to its crawl delay.
+    fn honor_robots(&mut self, robots: Robots) {
+        if let Some(crawl_delay) = robots.crawl_delay() {
+            let interval = self.rate_limit.as_ref().map(|rate_limit| rate_limit.interval());
+            if interval.is_none_or(|interval| interval < crawl_delay) {
+                debug!(?crawl_delay, "honoring the crawl delay of robots.txt");
+                self.rate_limit = Some(Arc::new(RateLimiter::new(crawl_delay)));
             }
-            break;
+        }
+        self.robots = Some(Arc::new(robots));
+    }
+
+    fn get(&self, url: &str) -> Result<String, CrawlerError> {
+        if let Some(robots) = &self.robots {
+            robots.check_url(url)?;
+        }
+        self.fetch(url, &[])
+    }
+
+    /// same as get for requests to the MAL API, which robots.txt of the
+    /// site does not cover.
+    fn get_api(&self, url: &str, headers: &[(&str, &str)]) -> Result<String, CrawlerError> {
+        self.fetch(url, headers)
+    }
+
+    /// send the request within the rate and host limits, retrying it as
+    /// the retry policy allows. Every attempt waits for its own slot.
+    fn fetch(&self, url: &str, headers: &[(&str, &str)]) -> Result<String, CrawlerError> {
+        let mut attempt = 1;
+        loop {
+            if let Some(rate_limit) = &self.rate_limit {
+                rate_limit.wait();
+            }
+            let result = {
+                let _permit = self.host_limit.acquire(url);
+                self.transport.get_with_headers(url, headers)
+            };
+            match result {
+                Err(err) => match self.retry.retry_after(&err, attempt) {
+                    Some(delay) => {
+                        warn!(%err, attempt, ?delay, "retrying request");
+                        if let Some(progress) = &self.progress {
+                            progress.on_retry(url, attempt);
+                        }
+                        thread::sleep(delay);
+                        attempt += 1;
+                    },
+                    None => return Err(err),
+                },
+                body => return body,
+            }
+        }
+    }
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
+
+/// Returns the broadcast of an anime page, e.g. "Saturdays at 23:00 (JST)".
+fn find_broadcast(parser: &dyn PageParser, body: &str) -> Option<String> {
+    if body.len() > MAX_PAGE_BYTES {
+        warn!(bytes = body.len(), "anime page too large, not parsing it");
+        return None;
+    }
+    // we are only interested in the airing hour of the anime, so that we
+    // can deduce the airing day correctly.
+    parser.info_field(body, HOUR_IDENTIFIER)
+}
+
+/// Returns the value of a labelled field of an anime page, e.g. the
+/// titles following "Synonyms:", with HTML entities decoded.
+fn parse_info_field(parser: &dyn PageParser, body: &str, label: &str) -> Option<String> {
+    parser.info_field(body, label).map(|value| unescape_html(&value))
+}
+
+/// texts of the links in the given markup, with HTML entities decoded.
+fn link_texts(html: &str) -> Vec<String> {
+    html.split("<a ").skip(1)
+        .filter_map(|link| link.split_once('>').and_then(|(_, rest)| rest.split_once("</a>")))
+        .map(|(text, _)| unescape_html(text.trim()))
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+/// seconds of an episode from its duration on an anime page, e.g.
+/// "24 min. per ep." or "1 hr. 50 min.". None for "Unknown".
+fn parse_duration(duration: &str) -> Option<u32> {
+    let words: Vec<&str> = duration.split_whitespace().collect();
+    let seconds: u32 = words.windows(2).filter_map(|pair| {
+        let amount = pair[0].parse::<u32>().ok()?;
+        let unit = match pair[1].trim_end_matches('.') {
+            "hr" | "hrs" => 3600,
+            "min" => 60,
+            "sec" => 1,
+            _ => return None,
+        };
+        Some(amount * unit)
+    }).sum();
+    Some(seconds).filter(|seconds| *seconds > 0)
+}
+
+/// Returns the genres, studios, source material, community score and
+/// episode length listed on an anime page.
+fn parse_metadata(parser: &dyn PageParser, body: &str) -> Metadata {
+    // "Genre:" and "Studio:" when there is only one.
+    let links = |labels: [&str; 2]| {
+        labels.iter().find_map(|label| parser.info_field(body, label)).map_or(Vec::new(), |html| link_texts(&html))
+    };
+    // the score is the text of the first tag, e.g. <span itemprop="ratingValue">9.10</span>.
+    let score = parser.info_field(body, "Score:")
+        .and_then(|score| {
+            let (_, rest) = score.split_once('>')?;
+            rest.split_once('<').and_then(|(score, _)| score.trim().parse::<f32>().ok())
+        });
+    Metadata { genres: links(["Genres:", "Genre:"]), studios: links(["Studios:", "Studio:"]),
+               source: parse_info_field(parser, body, "Source:").filter(|source| !source.is_empty() && source != "Unknown"),
+               score, duration: parser.info_field(body, "Duration:").and_then(|duration| parse_duration(&duration)) }
+}
+
+/// Returns the synonyms listed on an anime page.
+fn parse_synonyms(parser: &dyn PageParser, body: &str) -> Vec<String> {
+    parse_info_field(parser, body, "Synonyms:").map_or(Vec::new(), |synonyms| {
+        synonyms.split(", ")
+                .filter(|synonym| !synonym.is_empty())
+                .map(String::from)
+                .collect()
+    })
+}
+
+/// Returns the native title listed on an anime page.
+fn parse_japanese_title(parser: &dyn PageParser, body: &str) -> Option<String> {
+    parse_info_field(parser, body, "Japanese:").filter(|title| !title.is_empty())
+}
+
+/// Parses the broadcast time (in JST) out of an anime page. Returns None if
+/// the page does not contain a broadcast time.
+fn parse_broadcast_time(parser: &dyn PageParser, body: &str) -> Option<NaiveTime> {
+    let broadcast = find_broadcast(parser, body)?;
+    let hour_min = broadcast.split(' ').nth(2)?;
+    let mut hour_min_tokenized = hour_min.split(':');
+    let hour = hour_min_tokenized.next()?.parse::<u32>().ok()?;
+    let min = hour_min_tokenized.next()?.parse::<u32>().ok()?;
+    NaiveTime::from_hms_opt(hour, min, 0)
+}
+
+/// Parses the broadcast slot out of an anime page.
+fn parse_broadcast(parser: &dyn PageParser, body: &str) -> Option<BroadcastSchedule> {
+    BroadcastSchedule::parse(&find_broadcast(parser, body)?)
+}
 
+/// broadcast slot of the anime at the given time in JST. Pages without the
+/// weekday, e.g. from the broadcast cache, air on the weekday the anime
+/// started on, which is what the list page gives before shifting.
+fn broadcast_schedule(anime: &AnimeAttributes, weekday: Option<Weekday>,
+                      time: Option<NaiveTime>) -> Option<BroadcastSchedule> {
+    let weekday = weekday.or_else(|| anime.airing_weekday())?;
+    time.map(|time| BroadcastSchedule::jst(weekday, time))
+}
+
+/// by how many days an airing day in JST has to be shifted to match the
+/// local time, given the broadcast time in JST.
+fn day_shift(jst: NaiveTime, minutes_to_jst: i32) -> i32 {
+    let local_minute = (jst.hour() * 60 + jst.minute()) as i32 - minutes_to_jst;
+    local_minute.div_euclid(24 * 60)
+}
+
+/// Parses the broadcast time out of an anime page and returns by how many
+/// days the airing day has to be shifted to match the local time. Returns
+/// None if the page does not contain a broadcast time.
+fn parse_animepage_body(parser: &dyn PageParser, body: &str, minutes_to_jst: i32) -> Option<i32> {
+    parse_broadcast_time(parser, body).map(|time| day_shift(time, minutes_to_jst))
+}
+
+/// local time of a broadcast time in JST.
+fn local_time(jst: NaiveTime, minutes_to_jst: i32) -> NaiveTime {
+    jst - chrono::Duration::minutes(minutes_to_jst as i64)
+}
+
+/// Returns the broadcast time in JST and the Japanese title of the anime,
+/// from the broadcast cache or by fetching its page. Cached pages without a
+/// Japanese title are fetched again if the crawl wants one.
+#[instrument(level = "debug", skip(sources))]
+fn get_anime_page(sources: &PageSources, anime_id: i32) -> Result<AnimePage, CrawlerError> {
+    if let Some(page) = cached_anime_page(sources, anime_id) {
+        return Ok(page);
+    }
+    let url = anime_url(&sources.base_url, anime_id);
+    let body = fetch_animepage(sources, &url)?;
+    Ok(read_anime_page(sources, anime_id, &url, &body))
+}
+
+/// the anime page from the broadcast cache, if the cache has everything the
+/// crawl wants of it.
+fn cached_anime_page(sources: &PageSources, anime_id: i32) -> Option<AnimePage> {
+    let cache = sources.broadcast_cache.as_deref()?;
+    let time = cache.get(anime_id)?;
+    let title_jp = cache.japanese_title(anime_id);
+    if (sources.japanese_titles && title_jp.is_none()) || sources.metadata {
+        return None;
+    }
+    audit::record(&anime_url(&sources.base_url, anime_id), None, 0, true);
+    Some(AnimePage { broadcast: time, weekday: None, title_jp: title_jp.map(String::from), metadata: None })
+}
+
+/// parse a fetched anime page and store it in the broadcast cache.
+fn read_anime_page(sources: &PageSources, anime_id: i32, url: &str, body: &str) -> AnimePage {
+    let parser = sources.parser.as_ref();
+    let page = AnimePage { broadcast: parse_broadcast_time(parser, body),
+                           weekday: parse_broadcast(parser, body).map(|schedule| schedule.weekday),
+                           title_jp: parse_japanese_title(parser, body),
+                           metadata: Some(parse_metadata(parser, body)) };
+    if page.broadcast.is_none() {
+        warn!(%url, "no broadcast hour found");
+    }
+    let cache = sources.broadcast_cache.as_deref();
+    if let Some(Err(err)) = cache.map(|cache| cache.record(anime_id, page.broadcast, page.title_jp.as_deref())) {
+        warn!(%err, "could not update broadcast cache");
+    }
+    page
+}
+
+/// url of the list page of the section of the user's list to crawl.
+fn animelist_url(base_url: &str, user_attrib: &UserAttributes) -> String {
+    format!("{}/animelist/{}?status={}", base_url, user_attrib.uname, user_attrib.list_status.number())
+}
+
+fn anime_url(base_url: &str, anime_id: i32) -> String {
+    format!("{}/anime/{}/", base_url, anime_id)
+}
+
+fn fetch_animepage(sources: &PageSources, url: &str) -> Result<String, CrawlerError> {
+    let started = Instant::now();
+    let body = sources.get(url)?;
+    debug!(duration_ms = elapsed_ms(started), bytes = body.len(), "fetched anime page");
+    Ok(body)
+}
+
+/// Page of the anime, warning if it could not be fetched.
+fn fetch_page(sources: &PageSources, anime_id: i32) -> FetchedPage {
+    match get_anime_page(sources, anime_id) {
+        Ok(page) => Some(page),
+        Err(err) => {
+            warn!(anime_id, %err, "keeping airing day");
+            None
         }
-        index += 1;
     }
+}
 
-    anime.update_airing_day(shifting);
+/// What the crawler reads from the page of an anime.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimeDetails {
+    pub id: i32,
+    /// broadcast as shown on the page, e.g. "Saturdays at 23:00 (JST)".
+    pub broadcast: Option<String>,
+    /// the broadcast slot, if the page gives a weekly one.
+    pub schedule: Option<BroadcastSchedule>,
+    /// alternative titles of the anime.
+    pub synonyms: Vec<String>,
+    /// native title of the anime.
+    pub title_jp: Option<String>,
+    pub genres: Vec<String>,
+    pub studios: Vec<String>,
+    /// source material, e.g. "Manga".
+    pub source: Option<String>,
+    /// community score.
+    pub score: Option<f32>,
+    /// length of an episode in seconds.
+    pub episode_seconds: Option<u32>,
+    /// days the airing day has to be shifted to match the local time.
+    pub day_shift: i32,
 }
 
-/// split the body of the html file by end of line
-/// character. Then, traverse through the vector
-/// and seek for <table class="list-table" data-items="[
-/// Once found, shrink the line via: s[..-3]
-fn parse_animelist_body(body: String, user_attrib: &UserAttributes, 
-            registered_words: &Trie) -> Vec<Arc<Mutex<Vec<AnimeAttributes>>>> {
-    let mut tokenized_body: Vec<&str> = body.split('\n').collect();
-    let target = "<table class=\"list-table\" data-items=\"[";
-    let t_len = target.len();
-    let mut raw_anime_list = "";
+/// Fetches the page of the given anime.
+pub fn get_anime_details(anime_id: i32, options: &CrawlOptions) -> Result<AnimeDetails, CrawlerError> {
+    let sources = PageSources::for_crawl(options);
+    let body = fetch_animepage(&sources, &anime_url(&sources.base_url, anime_id))?;
+    Ok(anime_details(anime_id, &body, &sources))
+}
 
-    for tk in tokenized_body.iter().rev() {
-        let trimmed_tk = tk.trim();
-        let tk_len = trimmed_tk.len();
-        if tk_len >= t_len {
-            if &trimmed_tk[..t_len] == target {
-                raw_anime_list = &trimmed_tk[t_len..tk_len-3];
+/// Parses an anime page saved to a file, e.g. from a browser, the same way
+/// get_anime_details parses a fetched one. The id is read from the canonical
+/// link of the page, 0 if the page has none.
+pub fn get_animepage_from_file(path: &Path, options: &CrawlOptions) -> Result<AnimeDetails, CrawlerError> {
+    let body = read_saved_page(path)?;
+    let anime_id = parse_anime_id(&body).unwrap_or(0);
+    Ok(anime_details(anime_id, &body, &PageSources::new(options)))
+}
+
+fn anime_details(anime_id: i32, body: &str, sources: &PageSources) -> AnimeDetails {
+    let parser = sources.parser.as_ref();
+    let metadata = parse_metadata(parser, body);
+    AnimeDetails { id: anime_id, broadcast: find_broadcast(parser, body),
+                   schedule: parse_broadcast(parser, body),
+                   synonyms: parse_synonyms(parser, body), title_jp: parse_japanese_title(parser, body),
+                   genres: metadata.genres, studios: metadata.studios, source: metadata.source,
+                   score: metadata.score, episode_seconds: metadata.duration,
+                   day_shift: parse_animepage_body(parser, body, sources.minutes_to_jst).unwrap_or(0) }
+}
+
+/// id of the anime of a page, from its canonical link, e.g.
+/// <link rel="canonical" href="https://myanimelist.net/anime/52991/Sousou_no_Frieren" />.
+fn parse_anime_id(body: &str) -> Option<i32> {
+    let (_, rest) = body.split_once("<link rel=\"canonical\" href=\"https://myanimelist.net/anime/")?;
+    rest.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+}
+
+/// contents of a page saved to a file.
+fn read_saved_page(path: &Path) -> Result<String, CrawlerError> {
+    std::fs::read_to_string(path).map_err(|err| CrawlerError::io(format!("could not read {}", path.display()), err))
+}
+
+/// Updates the airing day of the anime from its broadcast time, and sets
+/// the local time it airs at and its Japanese title. Pages without a
+/// broadcast time do not shift.
+fn update_precise_airing_day(anime: &mut AnimeAttributes, sources: &PageSources) {
+    if restore_from_checkpoint(anime, sources) {
+        return;
+    }
+    let page = match &sources.page_cache {
+        Some(page_cache) => page_cache.page(sources, anime.id),
+        None => fetch_page(sources, anime.id),
+    };
+    if let Some(page) = page {
+        apply_anime_page(anime, &page, sources);
+    }
+}
+
+/// true if the crawl has to look at the page of the anime.
+fn needs_anime_page(anime: &AnimeAttributes, sources: &PageSources) -> bool {
+    anime.should_get_precise_day() || (sources.broadcast_times && anime.is_airing)
+        || sources.japanese_titles || sources.metadata
+}
+
+/// update the anime from the checkpoint of an interrupted crawl, false if
+/// the interrupted crawl did not get to it. The checkpoint keeps no
+/// metadata, crawls that want it fetch every page again.
+fn restore_from_checkpoint(anime: &mut AnimeAttributes, sources: &PageSources) -> bool {
+    if sources.metadata {
+        return false;
+    }
+    let checkpoint = match sources.checkpoint.as_deref() {
+        Some(checkpoint) => checkpoint,
+        None => return false,
+    };
+    let (shifting, time) = match checkpoint.get(anime.id) {
+        Some(fetched) => fetched,
+        None => return false,
+    };
+    anime.broadcast = broadcast_schedule(anime, None, time);
+    // finished entries are only fetched for their title.
+    if anime.is_airing {
+        anime.update_airing_day(shifting);
+    }
+    anime.airing_time = time.map(|time| local_time(time, sources.minutes_to_jst));
+    if let Some(title_jp) = checkpoint.japanese_title(anime.id) {
+        anime.title_jp = String::from(title_jp);
+    }
+    true
+}
+
+/// update the anime from its page and checkpoint the result.
+fn apply_anime_page(anime: &mut AnimeAttributes, page: &AnimePage, sources: &PageSources) {
+    let diff = sources.minutes_to_jst;
+    let time = page.broadcast;
+    let shifting = time.map_or(0, |time| day_shift(time, diff));
+    anime.broadcast = broadcast_schedule(anime, page.weekday, time);
+    if anime.is_airing {
+        anime.update_airing_day(shifting);
+    }
+    anime.airing_time = time.map(|time| local_time(time, diff));
+    if let Some(title_jp) = &page.title_jp {
+        anime.title_jp = title_jp.clone();
+    }
+    if let Some(metadata) = &page.metadata {
+        anime.genres = metadata.genres.clone();
+        anime.studios = metadata.studios.clone();
+        anime.source = metadata.source.clone();
+        anime.mean_score = metadata.score;
+        anime.episode_seconds = metadata.duration;
+    }
+    let checkpoint = sources.checkpoint.as_deref();
+    if let Some(Err(err)) = checkpoint.map(|checkpoint| {
+        checkpoint.record(anime.id, shifting, time, page.title_jp.as_deref())
+    }) {
+        warn!(%err, "could not update checkpoint");
+    }
+}
+
+/// the list page, or why the user has none to crawl: MAL answers 404 for
+/// users that do not exist and a notice for lists only their owner sees.
+fn check_list_page(user_attrib: &UserAttributes, fetched: Result<String, CrawlerError>) -> Result<String, CrawlerError> {
+    match fetched {
+        Err(CrawlerError::Status { status: 404, .. }) => Err(CrawlerError::UserNotFound(user_attrib.uname.clone())),
+        Ok(body) if body.contains(LIST_RESTRICTED_NOTICE) => Err(CrawlerError::ListPrivate(user_attrib.uname.clone())),
+        fetched => fetched,
+    }
+}
+
+/// attribute holding the entries of a list of the modern template, as
+/// HTML-escaped JSON.
+const DATA_ITEMS_ATTRIBUTE: &str = " data-items=\"";
+
+/// Template of a list page. Users can switch their list between MAL's
+/// modern template, which embeds the entries as JSON in a data-items
+/// attribute, and the classic one, which renders them as table rows. The
+/// element carrying data-items depends on the style of the modern list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListLayout {
+    Modern,
+    Classic,
+    Unknown,
+}
+
+impl std::fmt::Display for ListLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ListLayout::Modern => write!(f, "modern list-table/data-items"),
+            ListLayout::Classic => write!(f, "classic table_header/animetitle"),
+            ListLayout::Unknown => write!(f, "unrecognized markup"),
+        }
+    }
+}
+
+/// guess the template of a list page from markers of its markup.
+pub fn detect_list_layout(body: &str) -> ListLayout {
+    if body.contains(DATA_ITEMS_ATTRIBUTE) {
+        ListLayout::Modern
+    } else if body.contains("class=\"animetitle\"") || body.contains("class=\"table_header\"") {
+        ListLayout::Classic
+    } else {
+        ListLayout::Unknown
+    }
+}
+
+/// Decodes the HTML entities of an attribute value or of page text, in a
+/// single pass so that "&amp;quot;" stays "&quot;". Unknown entities are
+/// kept as they are.
+fn unescape_html(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        // entities are short, do not look for their end past a few bytes.
+        let decoded = rest.bytes().take(12).position(|b| b == b';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "quot" => Some('"'),
+                "amp" => Some('&'),
+                "apos" => Some('\''),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                entity => entity.strip_prefix('#').and_then(|code| {
+                    match code.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => code.parse::<u32>().ok(),
+                    }
+                }).and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            },
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// the user with the date format guessed from the start dates of their
+/// list. Lists whose dates all read both ways are taken as day-month-year,
+/// any entry of them parses either way.
+fn with_detected_date_format(user_attrib: &UserAttributes, entries: &[(usize, AnimeAttributes)]) -> UserAttributes {
+    let start_dates = entries.iter().map(|(_, entry)| &*entry.start_date).filter(|date| !date.is_empty());
+    let format = infer_date_format(start_dates).unwrap_or_else(|| {
+        debug!("start dates read both ways, assuming day-month-year");
+        "1"
+    });
+    let mut detected = user_attrib.clone();
+    let _ = detected.set_date_format(String::from(format));
+    debug!(format = %detected.date_format, "detected the date format of the list");
+    detected
+}
+
+fn load_json_url(base_url: &str, user_attrib: &UserAttributes, offset: usize) -> String {
+    format!("{}/animelist/{}/load.json?status={}&offset={}", base_url,
+            user_attrib.uname, user_attrib.list_status.number(), offset)
+}
+
+/// The entries of a list as fetched so far. The list page only embeds the
+/// first LIST_PAGE_SIZE entries, the rest are loaded from load.json a page
+/// at a time, as the list page itself does while the user scrolls.
+struct ListPayload {
+    items: Vec<Value>,
+    report: ParseReport,
+    /// entries of the last page, a full page may be followed by another.
+    last_page: usize,
+}
+
+impl ListPayload {
+    /// the entries of a list page in either template, an error if the page
+    /// is in neither.
+    fn from_page(body: &str, user_attrib: &UserAttributes) -> Result<Self, CrawlerError> {
+        match detect_list_layout(body) {
+            ListLayout::Modern => Ok(ListPayload::from_body(body)),
+            ListLayout::Classic if body.len() <= MAX_PAGE_BYTES => {
+                // classic lists are not paged, the page holds all of it.
+                let items = classic::list_items(body, user_attrib.list_status.number());
+                debug!(entries = items.len(), "read a list of the classic template");
+                Ok(ListPayload { items, report: ParseReport::new(), last_page: 0 })
+            },
+            ListLayout::Classic => Ok(ListPayload::from_body(body)),
+            layout => Err(CrawlerError::UnsupportedLayout(layout.to_string())),
+        }
+    }
+
+    /// traverse the lines of the html file from the end
+    /// and seek for the data-items attribute of the list table.
+    /// Once found, decode the HTML-escaped attribute and parse
+    /// it as the JSON array of the list entries.
+    fn from_body(body: &str) -> Self {
+        let mut raw_anime_list = None;
+        let mut report = ParseReport::new();
+        if body.len() > MAX_PAGE_BYTES {
+            warn!(bytes = body.len(), "list page too large, not parsing it");
+            report.limit_reached = Some(format!("page larger than {} bytes", MAX_PAGE_BYTES));
+            return ListPayload { items: Vec::new(), report, last_page: 0 };
+        }
+
+        for tk in body.rsplit('\n') {
+            if let Some((_, rest)) = tk.split_once(DATA_ITEMS_ATTRIBUTE) {
+                // the value is escaped, so its first quote closes it.
+                raw_anime_list = rest.split('"').next();
                 break;
             }
         }
+
+        let items: Vec<Value> = match raw_anime_list.map(|raw| serde_json::from_str(&unescape_html(raw))) {
+            Some(Ok(items)) => items,
+            Some(Err(err)) => {
+                warn!(%err, "could not decode the list payload");
+                report.invalid_payload = Some(err.to_string());
+                Vec::new()
+            },
+            None => Vec::new(),
+        };
+        ListPayload { last_page: items.len(), items, report }
     }
 
-    tokenized_body = raw_anime_list.split("&quot;").collect(); 
-    let mut index = 0;
-    let mut anime_list: Vec<Arc<Mutex<Vec<AnimeAttributes>>>> = Vec::new();
-    let mut current_anime_entry = AnimeAttributes::new();
-    let mut current_chunk: Vec<AnimeAttributes> = Vec::new();
-    let mut ignore_enabled = false;
+    /// url of the next page of the list, None once a page came back short.
+    fn next_page_url(&self, base_url: &str, user_attrib: &UserAttributes) -> Option<String> {
+        if self.last_page < LIST_PAGE_SIZE || self.items.len() >= MAX_LIST_ENTRIES {
+            return None;
+        }
+        Some(load_json_url(base_url, user_attrib, self.items.len()))
+    }
 
-    let num_threads: usize = 4;
-    let chunk_size: usize = std::cmp::max(1, anime_list.len() / num_threads);
+    /// add the fetched page. If it could not be fetched or decoded, the list
+    /// ends with the entries read so far.
+    fn add_page(&mut self, fetched: Result<String, CrawlerError>) {
+        let page = fetched.and_then(|body| Ok(serde_json::from_str::<Vec<Value>>(&body)?));
+        match page {
+            Ok(page) => {
+                debug!(offset = self.items.len(), entries = page.len(), "fetched list page");
+                self.last_page = page.len();
+                self.items.extend(page);
+            },
+            Err(err) => {
+                warn!(%err, offset = self.items.len(), "could not load the rest of the list");
+                self.report.incomplete = Some(format!("entries from {} on: {}", self.items.len(), err));
+                self.last_page = 0;
+            }
+        }
+    }
+}
 
-    while index < tokenized_body.len() {
-        let token = tokenized_body[index]; 
+#[instrument(level = "debug", skip_all, fields(entries = payload.items.len()))]
+fn parse_list_payload(payload: ListPayload, user_attrib: &UserAttributes, registered_words: &Trie,
+            hooks: &HookRegistry, minutes_to_jst: i32) -> (Vec<AnimeAttributes>, ParseReport) {
+    let started = Instant::now();
+    let today = config::local_weekday(Utc::now(), minutes_to_jst);
+    let ListPayload { items, mut report, .. } = payload;
+    let mut anime_list: Vec<AnimeAttributes> = Vec::new();
 
-        if !ignore_enabled && (token == "}" || token == "},{") {
-            // this concludes an anime entry.
-            current_chunk.push(current_anime_entry);
-            if (index + 1) % chunk_size == 0 {
-                anime_list.push(Arc::new(Mutex::new(current_chunk)));
-                current_chunk = Vec::new();
+    // entries that failed to parse are skipped rather than aborting the
+    // whole list.
+    let mut entries = Vec::with_capacity(items.len());
+    for (position, mut item) in items.into_iter().enumerate() {
+        if let Value::Object(fields) = &mut item {
+            let unrecognized = fields.keys().filter(|key| {
+                !registered_words.contains_word(key) && !IGNORED_LIST_KEYS.contains(&&*registered_words.fold(key))
+            });
+            for key in unrecognized {
+                report.unrecognized_key(key);
             }
-            current_anime_entry = AnimeAttributes::new();
-        } else if token == ":[{" || token == ":{" {
-            ignore_enabled = true;
-        } else if token == "}]," || token == "}," {
-            ignore_enabled = false;
-        } else if !ignore_enabled && registered_words.contains_word(token){
-            // check if this word is registered.
-            match current_anime_entry.register_attrib(user_attrib, token, 
-                        tokenized_body[index + 1], tokenized_body[index + 2]) {
-                Ok(i_forward) => {
-                    index += i_forward;
-                },
-                Err(err) => {
-                    println!("Error occured: {}", err);
-                    panic!("Error while inserting following token: {}, index:{} len:{} ", 
-                            token, index, tokenized_body.len());
+            // a folded trie also knows keys in another case or width, they
+            // are renamed to the registered spelling.
+            if registered_words.is_folded() {
+                let renamed: Vec<(String, String)> = fields.keys().filter(|key| registered_words.contains_word(key))
+                    .map(|key| (key.clone(), registered_words.fold(key).into_owned()))
+                    .filter(|(key, folded)| key != folded)
+                    .collect();
+                for (key, folded) in renamed {
+                    if let Some(value) = fields.remove(&key) {
+                        fields.insert(folded, value);
+                    }
                 }
             }
-        }         
+        }
+        match serde_json::from_value::<AnimeAttributes>(item) {
+            Ok(mut entry) => {
+                entry.set_today(today);
+                entries.push((position, entry));
+            },
+            Err(err) => {
+                warn!(%err, "skipping malformed entry");
+                report.skipped_entry(position, CrawlerError::from(err).to_string());
+            }
+        }
+    }
 
-        index += 1;
+    let detected;
+    let user_attrib = if user_attrib.has_date_format() {
+        user_attrib
+    } else {
+        detected = with_detected_date_format(user_attrib, &entries);
+        &detected
+    };
+    for (position, mut anime) in entries {
+        if anime_list.len() >= MAX_LIST_ENTRIES {
+            report.limit_reached = Some(format!("more than {} entries", MAX_LIST_ENTRIES));
+            break;
+        }
+        // the airing day follows from the start date, in the user's format.
+        let start_date = anime.start_date.clone();
+        let parsed = if start_date.is_empty() { Ok(()) } else { anime.set_start_date(user_attrib, &start_date) };
+        match parsed {
+            Ok(()) => {
+                if hooks.entry_parsed(&anime) {
+                    anime_list.push(anime);
+                }
+            },
+            Err(err) => {
+                warn!(%err, "skipping malformed entry");
+                report.skipped_entry(position, err.to_string());
+            }
+        }
     }
 
-    anime_list 
+    debug!(entries = anime_list.len(), duration_ms = elapsed_ms(started), "parsed list");
+    if let Some(limit) = &report.limit_reached {
+        warn!(%limit, "list cut short by a parsing limit");
+    }
+    if !report.is_clean() {
+        debug!(unrecognized_keys = ?report.unrecognized_keys,
+               skipped_entries = report.skipped_entries.len(), "parse report");
+    }
+    (anime_list, report)
 }
 
-/// Given the user, get the anime list in their watching list. 
-pub fn get_animelist(user_attrib: &UserAttributes, 
-        registered_words: &Trie) -> Result<Vec<AnimeAttributes>, 
-                                           Box<dyn std::error::Error>> {
-    let url = format!("https://myanimelist.net/animelist/{}?status=1", &user_attrib.uname);
-    let res = reqwest::blocking::get(url)?;
-    let body = res.text()?;
+/// Entry point of the list page fuzz target in fuzz/, not part of the API.
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub fn fuzz_list_page(body: &str) {
+    let registered_words = crate::config::REGISTERED_WORDS.to_vec();
+    let trie = Trie::new(Some(&registered_words));
+    let mut user = UserAttributes::new(String::from("fuzz"));
+    let _ = user.set_date_format(String::from("1"));
+    detect_list_layout(body);
+    parse_list_payload(ListPayload::from_body(body), &user, &trie, &HookRegistry::new(), 0);
+}
 
-    let mut anime_list = parse_animelist_body(body, 
-                                              user_attrib, registered_words);
+/// Entry point of the anime page fuzz target in fuzz/, not part of the API.
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub fn fuzz_anime_page(body: &str) {
+    parse_animepage_body(&MarkupParser, body, config::TIME_DIFF_TO_JST * 60);
+}
 
-    let mut result: Vec<AnimeAttributes> = Vec::new();
+/// Updates the airing day of every entry whose day is ambiguous by fetching
+/// its anime page. Entries are handed to a pool of workers over a channel and
+/// sent back, together with their position, over another one so that the
+/// order of the list is preserved.
+fn update_airing_days(anime_list: Vec<AnimeAttributes>, cancel: &CancellationToken, keep_partial: bool,
+                      sources: PageSources) -> Result<Vec<AnimeAttributes>, CrawlerError> {
+    let (job_sender, job_receiver) = mpsc::channel::<(usize, AnimeAttributes)>();
+    let (result_sender, result_receiver) = mpsc::channel::<(usize, AnimeAttributes)>();
 
-    if anime_list.len() == 0 {
-        return Ok(result);
+    let mut result: Vec<Option<AnimeAttributes>> = Vec::with_capacity(anime_list.len());
+    let mut num_jobs = 0;
+    for (index, anime) in anime_list.into_iter().enumerate() {
+        if needs_anime_page(&anime, &sources) {
+            // the receiver outlives this loop, so sending cannot fail.
+            let _ = job_sender.send((index, anime));
+            result.push(None);
+            num_jobs += 1;
+        } else {
+            result.push(Some(anime));
+        }
+    }
+    drop(job_sender);
+    if let Some(progress) = &sources.progress {
+        progress.on_list_parsed(result.len(), num_jobs);
     }
-     
-    let mut threads = Vec::new();
 
-    // partition the anime list to utilize multi-threading to parse
-    // anime pages. While doing so, updage the airing date of animes.
-    for chunk in anime_list.iter_mut() {
-        let cloned_chunk = Arc::clone(&chunk);
+    // the receiving end is only locked while a worker takes its next job.
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+    let mut workers = Vec::new();
+    for _ in 0..std::cmp::min(sources.workers, num_jobs) {
+        let job_receiver = Arc::clone(&job_receiver);
+        let result_sender = result_sender.clone();
+        let cancel = cancel.clone();
+        let sources = sources.clone();
+        let parent_span = tracing::Span::current();
 
-        let handle = thread::spawn(move || {
-            for anime in cloned_chunk.lock().unwrap().iter_mut() {
-                if anime.should_get_precise_day() {
-                    update_precise_airing_day(anime); 
+        workers.push(thread::spawn(move || {
+            let _entered = parent_span.enter();
+            while !cancel.is_cancelled() {
+                let job = match job_receiver.lock() {
+                    Ok(receiver) => receiver.recv(),
+                    Err(_) => break,
+                };
+                let (index, mut anime) = match job {
+                    Ok(job) => job,
+                    // every job has been taken.
+                    Err(_) => break,
+                };
+                update_precise_airing_day(&mut anime, &sources);
+                if result_sender.send((index, anime)).is_err() {
+                    break;
                 }
             }
-        });
+        }));
+    }
+    drop(result_sender);
 
-        threads.push(handle);
+    for (done, (index, anime)) in result_receiver.into_iter().enumerate() {
+        result[index] = Some(anime);
+        if let Some(progress) = &sources.progress {
+            progress.on_page_fetched(done + 1, num_jobs);
+        }
+    }
+    for worker in workers {
+        if worker.join().is_err() {
+            warn!("an anime page worker panicked, its entry is dropped");
+        }
     }
 
-    for th in threads {
-        th.join().unwrap();
+    if cancel.is_cancelled() {
+        if !keep_partial {
+            return Err(CrawlerError::Cancelled);
+        }
+        // the entries no worker got to are still waiting in the channel.
+        if let Ok(receiver) = job_receiver.lock() {
+            for (index, anime) in receiver.try_iter() {
+                result[index] = Some(anime);
+            }
+        }
     }
+    Ok(result.into_iter().flatten().collect())
+}
 
-    // combine the result.
-    for chunk in anime_list.iter() {
-        for anime in chunk.lock().unwrap().iter() {
-            result.push(anime.clone());
+/// what the report of a crawl cancelled while fetching anime pages says.
+fn cancelled_crawl() -> String {
+    String::from("the crawl was cancelled before every anime page was fetched")
+}
+
+/// Connect to the site of the options in the background, so that the
+/// first request of a crawl finds a pooled connection instead of paying
+/// for DNS, TCP and TLS itself. The request, for robots.txt, counts against
+/// the rate and host limits like any other.
+pub fn warm_up(options: &CrawlOptions) -> thread::JoinHandle<()> {
+    let sources = PageSources::new(options);
+    thread::spawn(move || {
+        let url = robots::robots_url(&sources.base_url);
+        let started = Instant::now();
+        match sources.get(&url) {
+            Ok(_) => debug!(%url, duration_ms = elapsed_ms(started), "connection warmed up"),
+            Err(err) => debug!(%url, %err, "could not warm up the connection"),
         }
+    })
+}
+
+/// Given the user, get the anime list in the section of their list
+/// selected by `list_status`, the watching list by default.
+pub fn get_animelist(user_attrib: &UserAttributes, 
+        registered_words: &Trie) -> Result<Vec<AnimeAttributes>, CrawlerError> {
+    get_animelist_with(user_attrib, registered_words, &CrawlOptions::default())
+}
+
+/// Same as get_animelist, but invokes the hooks and honors the cancellation
+/// token of the given options.
+pub fn get_animelist_with(user_attrib: &UserAttributes, registered_words: &Trie,
+        options: &CrawlOptions) -> Result<Vec<AnimeAttributes>, CrawlerError> {
+    crawl(user_attrib, registered_words, options).map(|result| result.entries)
+}
+
+/// Same as get_animelist_with, but also returns what the parser came across
+/// and could not handle.
+pub fn crawl(user_attrib: &UserAttributes, registered_words: &Trie,
+        options: &CrawlOptions) -> Result<CrawlResult, CrawlerError> {
+    crawl_animelist(user_attrib, registered_words, options, PageSources::for_crawl(options))
+}
+
+/// Parses a list page saved to a file, e.g. from a browser, without going
+/// to the network. No anime page is fetched, so entries keep the airing day
+/// the list gives, and only the entries on the saved page are read; the
+/// report tells if the list has more.
+pub fn get_animelist_from_file(path: &Path, user_attrib: &UserAttributes, registered_words: &Trie,
+        options: &CrawlOptions) -> Result<CrawlResult, CrawlerError> {
+    let started = Instant::now();
+    let body = check_list_page(user_attrib, read_saved_page(path))?;
+    let payload = ListPayload::from_page(&body, user_attrib)?;
+    let more_pages = payload.next_page_url(MAL_URL, user_attrib).is_some();
+    let (anime_list, mut report) = parse_list_payload(payload, user_attrib, registered_words, &options.hooks,
+                                                      minutes_to_jst(options));
+    if more_pages {
+        report.incomplete = Some(format!("the saved page holds the first {} entries of the list", LIST_PAGE_SIZE));
     }
+    Ok(finish_crawl(anime_list, report, &options.hooks, started))
+}
 
-    Ok(result)
+/// Crawls the lists of several users concurrently. Anime pages go through a
+/// cache shared by all of the crawls (the one of the options, or a new one),
+/// so an anime on several lists is fetched once. The crawls also share the
+/// rate limit. Results are in the order of `users`.
+pub fn get_animelists(users: &[UserAttributes], registered_words: &Trie,
+        options: &CrawlOptions) -> Vec<Result<Vec<AnimeAttributes>, CrawlerError>> {
+    let mut sources = PageSources::for_crawl(options);
+    let page_cache = options.page_cache.clone().unwrap_or_default();
+    sources.page_cache = Some(Arc::clone(&page_cache));
+
+    let results = thread::scope(|scope| {
+        let crawls: Vec<_> = users.iter().map(|user_attrib| {
+            let sources = sources.clone();
+            let parent_span = tracing::Span::current();
+            scope.spawn(move || {
+                let _entered = parent_span.enter();
+                crawl_animelist(user_attrib, registered_words, options, sources)
+            })
+        }).collect();
+
+        crawls.into_iter().map(|crawl| {
+            let result = crawl.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            result.map(|result| result.entries)
+        }).collect()
+    });
+    info!(pages = page_cache.len(), hits = page_cache.hits(), misses = page_cache.misses(), "anime page cache");
+    results
 }
 
+#[instrument(skip_all, fields(user = %user_attrib.uname))]
+fn crawl_animelist(user_attrib: &UserAttributes, registered_words: &Trie, options: &CrawlOptions,
+        mut sources: PageSources) -> Result<CrawlResult, CrawlerError> {
+    let started = Instant::now();
+    let hooks = &options.hooks;
+    if options.cancel.is_cancelled() {
+        return Err(CrawlerError::Cancelled);
+    }
+    // Japanese titles are only on the anime pages.
+    sources.japanese_titles |= user_attrib.title_language == TitleLanguage::Japanese;
+
+    if let Some(auth) = &options.api {
+        let fetched = info_span!("api_fetch").in_scope(|| api::fetch_animelist(user_attrib, auth, &sources));
+        match fetched {
+            Ok(anime_list) => {
+                let anime_list = anime_list.into_iter().filter(|anime| hooks.entry_parsed(anime)).collect();
+                return Ok(finish_crawl(anime_list, ParseReport::new(), hooks, started));
+            },
+            Err(err) => warn!(%err, "could not read the list from the MAL API, scraping the list page"),
+        }
+        if options.cancel.is_cancelled() {
+            return Err(CrawlerError::Cancelled);
+        }
+    }
+
+    let url = animelist_url(&sources.base_url, user_attrib);
+    let body = info_span!("list_fetch", %url).in_scope(|| {
+        let body = check_list_page(user_attrib, sources.get(&url))?;
+        debug!(duration_ms = elapsed_ms(started), bytes = body.len(), "fetched list");
+        Ok::<String, CrawlerError>(body)
+    })?;
+    if options.cancel.is_cancelled() {
+        return Err(CrawlerError::Cancelled);
+    }
+    // a dry run has no list to parse, the fetch above reported its url.
+    if options.transport.is_dry_run() {
+        return Ok(CrawlResult::default());
+    }
+
+    let mut payload = ListPayload::from_page(&body, user_attrib)?;
+    while let Some(url) = payload.next_page_url(&sources.base_url, user_attrib) {
+        if options.cancel.is_cancelled() {
+            return Err(CrawlerError::Cancelled);
+        }
+        payload.add_page(info_span!("list_page_fetch", %url).in_scope(|| sources.get(&url)));
+    }
+    let (anime_list, mut report) = parse_list_payload(payload, user_attrib, registered_words, hooks, sources.minutes_to_jst);
+
+    let checkpoint = open_checkpoint(user_attrib, options);
+    sources.checkpoint = checkpoint.clone();
+    let result = update_airing_days(anime_list, &options.cancel, options.partial_results, sources)?;
+    // the checkpoint of a cancelled crawl is kept for the next one to resume.
+    if options.cancel.is_cancelled() {
+        report.incomplete = Some(cancelled_crawl());
+    } else {
+        finish_checkpoint(checkpoint);
+    }
+
+    Ok(finish_crawl(result, report, hooks, started))
+}
+
+/// the checkpoint of the user's crawl, if the options ask to resume.
+fn open_checkpoint(user_attrib: &UserAttributes, options: &CrawlOptions) -> Option<Arc<Checkpoint>> {
+    if !options.resume {
+        return None;
+    }
+    match Checkpoint::for_user(&user_attrib.uname, minutes_to_jst(options)) {
+        Ok(checkpoint) => {
+            if !checkpoint.is_empty() {
+                info!(fetched = checkpoint.len(), "resuming interrupted crawl");
+            }
+            Some(Arc::new(checkpoint))
+        },
+        Err(err) => {
+            warn!(%err, "crawling without a checkpoint");
+            None
+        }
+    }
+}
+
+/// remove the checkpoint of a completed crawl, there is nothing left to
+/// resume.
+fn finish_checkpoint(checkpoint: Option<Arc<Checkpoint>>) {
+    if let Some(Ok(checkpoint)) = checkpoint.map(Arc::try_unwrap) {
+        if let Err(err) = checkpoint.finish() {
+            warn!(%err, "could not remove checkpoint");
+        }
+    }
+}
+
+/// run the hooks of a completed crawl.
+fn finish_crawl(entries: Vec<AnimeAttributes>, report: ParseReport, hooks: &HookRegistry,
+                started: Instant) -> CrawlResult {
+    for anime in entries.iter().filter(|anime| anime.is_airing_today()) {
+        hooks.airing_today(anime);
+    }
+    hooks.crawl_finished(&entries);
+    debug!(entries = entries.len(), duration_ms = elapsed_ms(started), "crawl finished");
+
+    CrawlResult { entries, report }
+}
+
+
+
+#[test]
+fn test_parse_animepage_body() {
+    let page = "<div class=\"spaceit_pad\">\n\
+                \x20   <span class=\"dark_text\">Broadcast:</span>\n\
+                \x20   Saturdays at 01:30 (JST)\n\
+                </div>";
+    let diff = config::TIME_DIFF_TO_JST * 60;
+    assert_eq!(parse_animepage_body(&MarkupParser, page, diff), Some(-1));
+    assert_eq!(parse_animepage_body(&MarkupParser, page, 0), Some(0));
+    assert_eq!(local_time(parse_broadcast_time(&MarkupParser, page).unwrap(), diff),
+               NaiveTime::from_hms_opt(19, 30, 0).unwrap());
+    // 03:00 JST is still the day before at UTC+5:30.
+    let india = config::minutes_to_jst_from(FixedOffset::east_opt(5 * 3600 + 1800).unwrap());
+    assert_eq!(parse_animepage_body(&MarkupParser, &page.replace("01:30", "03:00"), india), Some(-1));
+    assert_eq!(local_time(NaiveTime::from_hms_opt(3, 0, 0).unwrap(), india), NaiveTime::from_hms_opt(23, 30, 0).unwrap());
+    assert_eq!(find_broadcast(&MarkupParser, page).as_deref(), Some("Saturdays at 01:30 (JST)"));
+    assert_eq!(parse_broadcast(&MarkupParser, page).map(|schedule| schedule.weekday), Some(Weekday::Sat));
+
+    let unknown = page.replace("Saturdays at 01:30 (JST)", "Unknown");
+    assert_eq!(parse_animepage_body(&MarkupParser, &unknown, diff), None);
+
+    // oversized pages are not parsed at all.
+    let mut huge = String::from(page);
+    huge.push_str(&" ".repeat(MAX_PAGE_BYTES));
+    assert_eq!(parse_animepage_body(&MarkupParser, &huge, diff), None);
+
+    // multibyte characters around byte 28 must not panic.
+    assert_eq!(parse_animepage_body(&MarkupParser, "    <span class=\"dark\">進撃の巨人 Broadcast:</span>", diff), None);
+}
+
+#[test]
+fn test_parse_animelist_body() {
+    let body = String::from("<html>\n\
+        <table class=\"list-table\" data-items=\"[{&quot;status&quot;:1,&quot;score&quot;:8,\
+        &quot;anime_rank&quot;:&quot;&quot;,&quot;anime_id&quot;:5081,&quot;anime_title&quot;:\
+        &quot;Bakemonogatari&quot;,&quot;anime_title_eng&quot;:&quot;&quot;},{&quot;status&quot;:1,\
+        &quot;score&quot;:&quot;x&quot;,&quot;anime_id&quot;:1,&quot;anime_title&quot;:&quot;Broken&quot;},\
+        {&quot;status&quot;:1,&quot;score&quot;:0,&quot;anime_id&quot;:457,&quot;anime_title&quot;:\
+        &quot;Mushishi&quot;,&quot;anime_title_eng&quot;:&quot;Mushi-Shi&quot;},{&quot;anime_id&quot;:41084,\
+        &quot;anime_title&quot;:86,&quot;anime_title_eng&quot;:&quot;86 &amp;quot;Eighty-Six&amp;quot;&quot;}]\" \
+        data-broadcasts=\"[]\">\n\
+        </html>");
+    let mut user = UserAttributes::new(String::from("test"));
+    user.set_date_format(String::from("1")).unwrap();
+    let registered_words = crate::config::REGISTERED_WORDS.to_vec();
+    let trie = Trie::new(Some(&registered_words));
+
+    let payload = ListPayload::from_body(&body);
+    let (anime_list, report) = parse_list_payload(payload, &user, &trie, &HookRegistry::new(), 0);
+    assert_eq!(anime_list.len(), 3);
+    assert_eq!((anime_list[0].id, anime_list[0].score), (5081, 8));
+    assert_eq!(anime_list[0].title, "Bakemonogatari");
+    assert_eq!(anime_list[1].title_eng, "Mushi-Shi");
+    assert_eq!(anime_list[2].title, "86");
+    assert_eq!(anime_list[2].title_eng, "86 &quot;Eighty-Six&quot;");
+    assert_eq!(report.unrecognized_keys.get("anime_rank"), Some(&1));
+    assert_eq!(report.skipped_entries.len(), 1);
+    assert_eq!(report.skipped_entries[0].position, 1);
+}
+
+#[test]
+fn test_parse_full_entries() {
+    // entries as MAL sends them, with every key it puts in them.
+    let body = include_str!("../fixtures/myanimelist.net_animelist_fixture_status_1.html");
+    let user = UserAttributes::new(String::from("fixture"));
+    let registered_words = crate::config::REGISTERED_WORDS.to_vec();
+    let trie = Trie::new(Some(&registered_words));
+
+    let (anime_list, report) = parse_list_payload(ListPayload::from_body(body), &user, &trie, &HookRegistry::new(), 0);
+    assert!(report.is_clean(), "{:?}", report);
+    assert_eq!(anime_list.len(), 2);
+    assert_eq!(anime_list[0].genres, vec!["Adventure", "Drama", "Fantasy"]);
+    assert_eq!(anime_list[0].studios, vec!["Madhouse"]);
+}
+
+#[test]
+fn test_parse_folded_keys() {
+    let body = String::from("<table data-items=\"[{&quot;Status&quot;:1,&quot;ANIME_ID&quot;:5081,\
+        &quot;ａｎｉｍｅ＿ｔｉｔｌｅ&quot;:&quot;Bakemonogatari&quot;}]\">");
+    let user = UserAttributes::new(String::from("test"));
+    let registered_words = crate::config::REGISTERED_WORDS.to_vec();
+
+    let trie = Trie::new_folded(Some(&registered_words));
+    let (anime_list, report) = parse_list_payload(ListPayload::from_body(&body), &user, &trie, &HookRegistry::new(), 0);
+    assert_eq!((anime_list[0].id, anime_list[0].title.as_str(), anime_list[0].status), (5081, "Bakemonogatari", 1));
+    assert!(report.unrecognized_keys.is_empty());
+
+    let trie = Trie::new(Some(&registered_words));
+    let (_, report) = parse_list_payload(ListPayload::from_body(&body), &user, &trie, &HookRegistry::new(), 0);
+    assert_eq!(report.unrecognized_keys.get("ANIME_ID"), Some(&1));
+}
+
+#[test]
+fn test_list_pages() {
+    let entry = |id: usize| format!("{{&quot;status&quot;:1,&quot;anime_id&quot;:{},&quot;anime_title&quot;:\
+                                     &quot;Anime {}&quot;}}", id, id);
+    let items: Vec<String> = (0..LIST_PAGE_SIZE).map(entry).collect();
+    let body = format!("<table class=\"list-table\" data-items=\"[{}]\">", items.join(","));
+    let mut user = UserAttributes::new(String::from("test"));
+    user.set_date_format(String::from("1")).unwrap();
+    let registered_words = crate::config::REGISTERED_WORDS.to_vec();
+    let trie = Trie::new(Some(&registered_words));
+
+    let mut payload = ListPayload::from_body(&body);
+    assert_eq!(payload.next_page_url(MAL_URL, &user).as_deref(),
+               Some("https://myanimelist.net/animelist/test/load.json?status=1&offset=300"));
+    payload.add_page(Ok(String::from("[{\"status\":1,\"anime_id\":300,\"anime_title\":\"Anime 300\"}]")));
+    // a page short of LIST_PAGE_SIZE is the last one.
+    assert_eq!(payload.next_page_url(MAL_URL, &user), None);
+    let (anime_list, report) = parse_list_payload(payload, &user, &trie, &HookRegistry::new(), 0);
+    assert_eq!(anime_list.len(), LIST_PAGE_SIZE + 1);
+    assert_eq!(anime_list[LIST_PAGE_SIZE].title, "Anime 300");
+    assert!(report.is_clean());
+
+    let mut payload = ListPayload::from_body(&body);
+    payload.add_page(Err(CrawlerError::Cancelled));
+    assert_eq!(payload.next_page_url(MAL_URL, &user), None);
+    let (anime_list, report) = parse_list_payload(payload, &user, &trie, &HookRegistry::new(), 0);
+    assert_eq!(anime_list.len(), LIST_PAGE_SIZE);
+    assert!(report.incomplete.is_some());
+}
+
+#[test]
+fn test_detected_date_format() {
+    let body = String::from(include_str!("../fixtures/myanimelist.net_animelist_fixture_status_1.html"));
+    let registered_words = crate::config::REGISTERED_WORDS.to_vec();
+    let trie = Trie::new(Some(&registered_words));
+    // 29-09-2023 can only be day-month-year.
+    let user = UserAttributes::new(String::from("fixture"));
+    let payload = ListPayload::from_body(&body);
+    let (anime_list, report) = parse_list_payload(payload, &user, &trie, &HookRegistry::new(), 0);
+    assert_eq!(anime_list.len(), 2);
+    assert!(report.skipped_entries.is_empty());
+    assert_eq!(anime_list[0].airing_weekday(), Some(chrono::Weekday::Fri));
+}
+
+#[test]
+fn test_unescape_html() {
+    assert_eq!(unescape_html("Journey&#039;s &amp;quot;End&quot; &#x41;&#66;"), "Journey's &quot;End\" AB");
+    assert_eq!(unescape_html("Fate/stay & &unknown; &#xD800; 葬送&"), "Fate/stay & &unknown; &#xD800; 葬送&");
+}
+
+#[test]
+fn test_cancelled_crawl_fetches_nothing() {
+    let mut anime = AnimeAttributes::new();
+    anime.is_airing = true;
+    anime.set_airing_weekday(Weekday::Fri);
+    anime.set_today(Weekday::Fri);
+    assert!(anime.should_get_precise_day());
+
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let sources = PageSources::new(&CrawlOptions::default());
+    assert!(matches!(update_airing_days(vec![anime.clone()], &cancel, false, sources.clone()),
+                     Err(CrawlerError::Cancelled)));
+    // the entry comes back as the list had it.
+    let partial = update_airing_days(vec![anime], &cancel, true, sources).unwrap();
+    assert_eq!(partial.len(), 1);
+    assert!(partial[0].should_get_precise_day());
+}
+
+#[test]
+fn test_replay_crawl() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let options = CrawlOptions { transport: Transport::Replay(fixtures), ..Default::default() };
+    let mut user = UserAttributes::new(String::from("fixture"));
+    user.set_date_format(String::from("1")).unwrap();
+    let registered_words = crate::config::REGISTERED_WORDS.to_vec();
+    let trie = Trie::new(Some(&registered_words));
+
+    let anime_list = get_animelist_with(&user, &trie, &options).unwrap();
+    assert_eq!(anime_list.len(), 2);
+    assert_eq!((anime_list[0].id, anime_list[0].score, anime_list[0].num_episodes), (52991, 9, 28));
+    assert_eq!(anime_list[0].title, "Sousou no Frieren");
+    assert!(!anime_list[0].is_finished());
+    assert_eq!(&*anime_list[1].start_date, "23-10-2005");
+    assert!(anime_list[1].is_rewatching && anime_list[1].is_finished());
+}
+
+#[test]
+fn test_detect_list_layout() {
+    let fixture = include_str!("../fixtures/myanimelist.net_animelist_fixture_status_1.html");
+    assert_eq!(detect_list_layout(fixture), ListLayout::Modern);
+    assert_eq!(detect_list_layout("<a href=\"/anime/457/Mushishi\" class=\"animetitle\">"),
+               ListLayout::Classic);
+    assert_eq!(detect_list_layout("<html><body>Maintenance</body></html>"), ListLayout::Unknown);
+
+    // styles of the modern list put data-items on other elements.
+    let styled = "<div class=\"list-block\">\n  <div class=\"list-unit\" data-items=\"[{&quot;anime_id&quot;:457}]\"></div>";
+    assert_eq!(detect_list_layout(styled), ListLayout::Modern);
+    let user = UserAttributes::new(String::from("fixture"));
+    assert_eq!(ListPayload::from_page(styled, &user).unwrap().items, vec![serde_json::json!({"anime_id": 457})]);
+    let classic = "<a href=\"/anime/457/Mushishi\" class=\"animetitle\"><span>Mushishi</span></a></td></tr>";
+    let payload = ListPayload::from_page(classic, &user).unwrap();
+    assert_eq!(payload.items[0]["anime_title"], "Mushishi");
+    assert_eq!(payload.next_page_url(MAL_URL, &user), None);
+    assert!(matches!(ListPayload::from_page("<html></html>", &user), Err(CrawlerError::UnsupportedLayout(_))));
+}
+
+#[test]
+fn test_check_list_page() {
+    let user = UserAttributes::new(String::from("nobody"));
+    let missing = Err(CrawlerError::Status { url: animelist_url(MAL_URL, &user), status: 404, retry_after: None });
+    assert_eq!(check_list_page(&user, missing).unwrap_err().to_string(), "there is no MAL user named nobody");
+    let restricted = format!("<div class=\"badresult\">{}.</div>", LIST_RESTRICTED_NOTICE);
+    assert!(matches!(check_list_page(&user, Ok(restricted)), Err(CrawlerError::ListPrivate(_))));
+    let fixture = include_str!("../fixtures/myanimelist.net_animelist_fixture_status_1.html");
+    assert!(check_list_page(&user, Ok(String::from(fixture))).is_ok());
+}
+
+#[test]
+fn test_parse_metadata() {
+    let page = include_str!("../fixtures/myanimelist.net_anime_52991.html");
+    let metadata = parse_metadata(&MarkupParser, page);
+    assert_eq!(metadata.genres, vec!["Adventure", "Drama", "Fantasy"]);
+    assert_eq!(metadata.studios, vec!["Madhouse"]);
+    assert_eq!(metadata.source.as_deref(), Some("Manga"));
+    assert_eq!(metadata.score, Some(9.1));
+    assert_eq!(metadata.duration, Some(24 * 60));
+    assert_eq!(parse_duration("1 hr. 50 min."), Some(110 * 60));
+    assert_eq!(parse_duration("30 sec. per ep."), Some(30));
+    assert_eq!(parse_duration("Unknown"), None);
+
+    let unscored = "<span class=\"dark_text\">Score:</span>\n<span itemprop=\"ratingValue\">N/A</span>\n\
+                    <span class=\"dark_text\">Genre:</span> <a href=\"/anime/genre/8/Drama\">Drama</a></div>";
+    assert_eq!(parse_metadata(&MarkupParser, unscored), Metadata { genres: vec![String::from("Drama")], ..Default::default() });
+}
+
+#[test]
+fn test_pages_from_file() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let registered_words = crate::config::REGISTERED_WORDS.to_vec();
+    let trie = Trie::new(Some(&registered_words));
+    let user = UserAttributes::new(String::from("fixture"));
+    let options = CrawlOptions { time_diff_to_jst: Some(0), ..Default::default() };
+
+    let list = fixtures.join("myanimelist.net_animelist_fixture_status_1.html");
+    let result = get_animelist_from_file(&list, &user, &trie, &options).unwrap();
+    assert_eq!(result.entries.len(), 2);
+    assert_eq!(result.entries[0].title, "Sousou no Frieren");
+    assert!(result.report.skipped_entries.is_empty() && result.report.incomplete.is_none());
+
+    let page = fixtures.join("myanimelist.net_anime_52991.html");
+    let details = get_animepage_from_file(&page, &options).unwrap();
+    assert_eq!(details.id, 52991);
+    assert_eq!(details.broadcast.as_deref(), Some("Fridays at 23:00 (JST)"));
+    assert_eq!(details.title_jp.as_deref(), Some("葬送のフリーレン"));
+    assert_eq!(details.day_shift, 0);
+
+    assert!(matches!(get_animelist_from_file(&page, &user, &trie, &options),
+                     Err(CrawlerError::UnsupportedLayout(_))));
+    assert!(matches!(get_animepage_from_file(&fixtures.join("missing.html"), &options), Err(CrawlerError::Io { .. })));
+}
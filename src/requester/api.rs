@@ -0,0 +1,267 @@
+// Client of the official MyAnimeList v2 REST API. Lists are read as JSON
+// instead of being scraped from the list page, so a change to MAL's markup
+// does not break the crawl. The broadcast of every entry comes with the
+// list, so no anime page has to be fetched either.
+//
+// Requests authenticate with the client id of a registered API client
+// (X-MAL-CLIENT-ID header) or with an OAuth access token of the user.
+
+use chrono::{Datelike, NaiveDate, NaiveTime, Utc, Weekday};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::anime::{AnimeAttributes, BroadcastSchedule, ListStatus, UserAttributes};
+use crate::config;
+use crate::error::CrawlerError;
+use super::{day_shift, local_time, PageSources};
+
+pub const API_URL: &str = "https://api.myanimelist.net/v2";
+
+// Entries per page of a list response, the most the API allows.
+const PAGE_LIMIT: usize = 1000;
+const LIST_FIELDS: &str = "list_status,num_episodes,status,start_date,alternative_titles,broadcast,\
+                           genres,studios,source,mean,average_episode_duration";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiAuth {
+    /// client id of an API client registered at myanimelist.net/apiconfig.
+    ClientId(String),
+    /// OAuth access token of the user.
+    AccessToken(String),
+}
+
+impl ApiAuth {
+    pub(super) fn header(&self) -> (&'static str, String) {
+        match self {
+            ApiAuth::ClientId(id) => ("X-MAL-CLIENT-ID", id.clone()),
+            ApiAuth::AccessToken(token) => ("Authorization", format!("Bearer {}", token)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ListResponse {
+    data: Vec<ListItem>,
+    #[serde(default)]
+    paging: Paging,
+}
+
+#[derive(Deserialize, Default)]
+struct Paging {
+    next: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ListItem {
+    node: Node,
+    list_status: Option<EntryStatus>,
+}
+
+#[derive(Deserialize)]
+struct Node {
+    id: i32,
+    title: String,
+    #[serde(default)]
+    alternative_titles: AlternativeTitles,
+    num_episodes: Option<i32>,
+    status: Option<String>,
+    start_date: Option<String>,
+    broadcast: Option<Broadcast>,
+    #[serde(default)]
+    genres: Vec<Named>,
+    #[serde(default)]
+    studios: Vec<Named>,
+    source: Option<String>,
+    mean: Option<f32>,
+    /// seconds.
+    average_episode_duration: Option<u32>,
+}
+
+/// a genre or studio.
+#[derive(Deserialize)]
+struct Named {
+    name: String,
+}
+
+#[derive(Deserialize, Default)]
+struct AlternativeTitles {
+    #[serde(default)]
+    synonyms: Vec<String>,
+    en: Option<String>,
+    ja: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Broadcast {
+    day_of_the_week: String,
+    start_time: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EntryStatus {
+    status: Option<String>,
+    #[serde(default)]
+    score: i32,
+    #[serde(default)]
+    num_episodes_watched: i32,
+    #[serde(default)]
+    is_rewatching: bool,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+    message: Option<String>,
+}
+
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    let weekday = match day {
+        "monday" => Weekday::Mon,
+        "tuesday" => Weekday::Tue,
+        "wednesday" => Weekday::Wed,
+        "thursday" => Weekday::Thu,
+        "friday" => Weekday::Fri,
+        "saturday" => Weekday::Sat,
+        "sunday" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+/// url of the first page of the given section of the user's list.
+pub fn animelist_url(uname: &str, status: ListStatus) -> String {
+    let filter = status.api_name().map_or(String::new(), |name| format!("status={}&", name));
+    format!("{}/users/{}/animelist?{}fields={}&limit={}", API_URL, uname, filter, LIST_FIELDS, PAGE_LIMIT)
+}
+
+/// source material as the anime page shows it, e.g. "Light novel" for
+/// light_novel.
+fn source_name(source: &str) -> String {
+    let source = source.replace('_', " ");
+    let mut chars = source.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => source,
+    }
+}
+
+fn to_anime(item: ListItem, user: &UserAttributes, minutes_to_jst: i32) -> AnimeAttributes {
+    let node = item.node;
+    let mut anime = AnimeAttributes::new();
+    anime.set_today(config::local_weekday(Utc::now(), minutes_to_jst));
+    anime.id = node.id;
+    anime.title = node.title;
+    anime.title_eng = node.alternative_titles.en.unwrap_or_default();
+    anime.title_jp = node.alternative_titles.ja.unwrap_or_default();
+    anime.synonyms = node.alternative_titles.synonyms;
+    anime.num_episodes = node.num_episodes.unwrap_or(0);
+    anime.genres = node.genres.into_iter().map(|genre| genre.name).collect();
+    anime.studios = node.studios.into_iter().map(|studio| studio.name).collect();
+    anime.source = node.source.as_deref().map(source_name);
+    anime.mean_score = node.mean;
+    anime.episode_seconds = node.average_episode_duration.filter(|seconds| *seconds > 0);
+    anime.is_airing = node.status.as_deref() == Some("currently_airing");
+    if let Some(list_status) = item.list_status {
+        anime.status = list_status.status.as_deref().and_then(ListStatus::from_api_name)
+                                        .map_or(0, ListStatus::number);
+        anime.score = list_status.score;
+        anime.num_watched_episodes = list_status.num_episodes_watched;
+        anime.is_rewatching = list_status.is_rewatching;
+    }
+
+    // the start date is shown in the user's format, like on the list page,
+    // or as day-month-year if the user did not give theirs.
+    let start_date = node.start_date.as_deref().and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+    let date_format = if user.has_date_format() { user.date_format.as_str() } else { "%d-%m-%Y" };
+    if let Some(start_date) = start_date {
+        anime.start_date = crate::intern::global().intern(&start_date.format(date_format).to_string());
+        anime.set_airing_weekday(start_date.weekday());
+        anime.first_aired = Some(start_date);
+    }
+
+    // the broadcast is in JST, shift it to the local day and time.
+    if let Some(broadcast) = node.broadcast {
+        let time = broadcast.start_time.as_deref().and_then(|time| NaiveTime::parse_from_str(time, "%H:%M").ok());
+        if let Some(weekday) = parse_weekday(&broadcast.day_of_the_week) {
+            anime.broadcast = time.map(|time| BroadcastSchedule::jst(weekday, time));
+            let weekday = match time.map_or(0, |time| day_shift(time, minutes_to_jst)) {
+                1 => weekday.succ(),
+                -1 => weekday.pred(),
+                _ => weekday,
+            };
+            anime.set_airing_weekday(weekday);
+            anime.airing_time = time.map(|time| local_time(time, minutes_to_jst));
+        }
+    }
+    anime
+}
+
+/// entries of a page of a list response and the url of the next page, if
+/// there is one. Broadcasts are shifted by `minutes_to_jst` to the local time.
+pub fn parse_animelist_response(body: &str, user: &UserAttributes,
+                                minutes_to_jst: i32) -> Result<(Vec<AnimeAttributes>, Option<String>), CrawlerError> {
+    let response: ListResponse = serde_json::from_str(body).map_err(|err| {
+        match serde_json::from_str::<ErrorResponse>(body) {
+            Ok(error) => CrawlerError::Api(error.message.unwrap_or(error.error)),
+            Err(_) => CrawlerError::Api(err.to_string()),
+        }
+    })?;
+    let entries = response.data.into_iter()
+                               .map(|item| to_anime(item, user, minutes_to_jst))
+                               .collect();
+    Ok((entries, response.paging.next))
+}
+
+/// every entry of the user's watching list, following the pages of the
+/// response.
+pub(super) fn fetch_animelist(user: &UserAttributes, auth: &ApiAuth,
+                              sources: &PageSources) -> Result<Vec<AnimeAttributes>, CrawlerError> {
+    let (name, value) = auth.header();
+    let mut entries = Vec::new();
+    let mut next = Some(animelist_url(&user.uname, user.list_status));
+    while let Some(url) = next {
+        let body = sources.get_api(&url, &[(name, &value)])?;
+        // a dry run has no response to parse, the fetch reported its url.
+        if sources.transport.is_dry_run() {
+            break;
+        }
+        let (page, next_page) = parse_animelist_response(&body, user, sources.minutes_to_jst)?;
+        debug!(%url, entries = page.len(), "fetched list page from the API");
+        entries.extend(page);
+        next = next_page;
+    }
+    Ok(entries)
+}
+
+
+#[test]
+fn test_parse_animelist_response() {
+    let body = r#"{"data":[{"node":{"id":52991,"title":"Sousou no Frieren",
+        "alternative_titles":{"synonyms":["Frieren at the Funeral"],"en":"Frieren: Beyond Journey's End",
+        "ja":"葬送のフリーレン"},"num_episodes":28,"status":"currently_airing","start_date":"2023-09-29",
+        "broadcast":{"day_of_the_week":"saturday","start_time":"01:30"},
+        "genres":[{"id":2,"name":"Adventure"},{"id":8,"name":"Drama"}],"studios":[{"id":11,"name":"Madhouse"}],
+        "source":"light_novel","mean":9.1,"average_episode_duration":1470},
+        "list_status":{"status":"watching","score":9,"num_episodes_watched":12,"is_rewatching":false}},
+        {"node":{"id":457,"title":"Mushishi","num_episodes":26,"status":"finished_airing","start_date":"2005-10-23"}}],
+        "paging":{"next":"https://api.myanimelist.net/v2/users/fixture/animelist?offset=2"}}"#;
+    let mut user = UserAttributes::new(String::from("fixture"));
+    user.set_date_format(String::from("1")).unwrap();
+
+    let (entries, next) = parse_animelist_response(body, &user, 6 * 60).unwrap();
+    assert_eq!(next.as_deref(), Some("https://api.myanimelist.net/v2/users/fixture/animelist?offset=2"));
+    assert_eq!((entries[0].id, entries[0].status, entries[0].score, entries[0].num_episodes), (52991, 1, 9, 28));
+    assert_eq!(entries[0].title_jp, "葬送のフリーレン");
+    assert_eq!(&*entries[0].start_date, "29-09-2023");
+    // Saturday 01:30 JST is Friday 19:30 six hours behind.
+    assert_eq!(entries[0].airing_weekday(), Some(Weekday::Fri));
+    assert_eq!(entries[0].airing_time, NaiveTime::from_hms_opt(19, 30, 0));
+    assert_eq!(entries[0].broadcast.as_ref().map(ToString::to_string).as_deref(), Some("Saturdays at 01:30 (JST)"));
+    assert_eq!(entries[0].genres, vec!["Adventure", "Drama"]);
+    assert_eq!((entries[0].source.as_deref(), entries[0].mean_score), (Some("Light novel"), Some(9.1)));
+    assert_eq!(entries[0].episode_seconds, Some(1470));
+    assert!(entries[1].is_finished());
+
+    let error = parse_animelist_response(r#"{"error":"invalid_token"}"#, &user, 6 * 60).unwrap_err();
+    assert_eq!(error.to_string(), "unexpected response from the MAL API: invalid_token");
+}
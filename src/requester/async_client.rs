@@ -0,0 +1,251 @@
+// Async variant of the crawl, for programs already running on tokio. The
+// anime pages of a list are fetched as concurrent tasks instead of by a pool
+// of worker threads, bounded by the number of workers and the per-host
+// request limit.
+//
+// It shares the parsers, caches, checkpoints and hooks of the blocking
+// crawl; only the requests and the waits of the rate limiter are async.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+
+use crate::anime::{AnimeAttributes, UserAttributes};
+use crate::error::CrawlerError;
+use crate::report::ParseReport;
+use crate::trie::Trie;
+use crate::titles::TitleLanguage;
+use super::api::{self, ApiAuth};
+use super::robots::{robots_url, Robots};
+use super::{anime_url, animelist_url, apply_anime_page, cached_anime_page, cancelled_crawl, check_list_page,
+            elapsed_ms, finish_checkpoint, finish_crawl, needs_anime_page, open_checkpoint,
+            parse_list_payload, read_anime_page, restore_from_checkpoint, CrawlOptions, CrawlResult,
+            FetchedPage, ListPayload, PageSources};
+
+impl PageSources {
+    /// same as get, awaiting the rate limiter and the response instead of
+    /// blocking on them.
+    async fn get_async(&self, url: &str, headers: &[(&str, &str)]) -> Result<String, CrawlerError> {
+        if let Some(robots) = &self.robots {
+            robots.check_url(url)?;
+        }
+        self.fetch_async(url, headers).await
+    }
+
+    /// same as fetch, sleeping on the runtime between attempts.
+    async fn fetch_async(&self, url: &str, headers: &[(&str, &str)]) -> Result<String, CrawlerError> {
+        let mut attempt = 1;
+        loop {
+            if let Some(rate_limit) = &self.rate_limit {
+                tokio::time::sleep(rate_limit.reserve()).await;
+            }
+            match self.transport.get_async(url, headers).await {
+                Err(err) => match self.retry.retry_after(&err, attempt) {
+                    Some(delay) => {
+                        warn!(%err, attempt, ?delay, "retrying request");
+                        if let Some(progress) = &self.progress {
+                            progress.on_retry(url, attempt);
+                        }
+                        tokio::time::sleep(delay).await;
+                        attempt += 1;
+                    },
+                    None => return Err(err),
+                },
+                body => return body,
+            }
+        }
+    }
+}
+
+async fn fetch_api_animelist(user: &UserAttributes, auth: &ApiAuth,
+                             sources: &PageSources) -> Result<Vec<AnimeAttributes>, CrawlerError> {
+    let (name, value) = auth.header();
+    let mut entries = Vec::new();
+    let mut next = Some(api::animelist_url(&user.uname, user.list_status));
+    while let Some(url) = next {
+        // robots.txt of the site does not cover the API.
+        let body = sources.fetch_async(&url, &[(name, &value)]).await?;
+        if sources.transport.is_dry_run() {
+            break;
+        }
+        let (page, next_page) = api::parse_animelist_response(&body, user, sources.minutes_to_jst)?;
+        entries.extend(page);
+        next = next_page;
+    }
+    Ok(entries)
+}
+
+/// the page of the anime from the broadcast cache or the network.
+async fn fetch_anime_page(sources: &PageSources, anime_id: i32) -> FetchedPage {
+    if let Some(page) = cached_anime_page(sources, anime_id) {
+        return Some(page);
+    }
+    let url = anime_url(&sources.base_url, anime_id);
+    let started = Instant::now();
+    match sources.get_async(&url, &[]).await {
+        Ok(body) => {
+            debug!(duration_ms = elapsed_ms(started), bytes = body.len(), "fetched anime page");
+            Some(read_anime_page(sources, anime_id, &url, &body))
+        },
+        Err(err) => {
+            warn!(anime_id, %err, "keeping airing day");
+            None
+        }
+    }
+}
+
+/// update the anime from the checkpoint, the page cache, the broadcast
+/// cache or its page.
+async fn update_precise_airing_day(mut anime: AnimeAttributes, sources: &PageSources) -> AnimeAttributes {
+    if restore_from_checkpoint(&mut anime, sources) {
+        return anime;
+    }
+    let page_cache = sources.page_cache.as_deref();
+    let page = match page_cache.and_then(|page_cache| page_cache.get(anime.id)) {
+        Some(page) => page,
+        None => {
+            let page = fetch_anime_page(sources, anime.id).await;
+            if let Some(page_cache) = page_cache {
+                page_cache.insert(anime.id, page.clone());
+            }
+            page
+        }
+    };
+    if let Some(page) = page {
+        apply_anime_page(&mut anime, &page, sources);
+    }
+    anime
+}
+
+/// Same as requester::crawl. Must be called from within a tokio runtime.
+pub async fn crawl(user_attrib: &UserAttributes, registered_words: &Trie,
+                   options: &CrawlOptions) -> Result<CrawlResult, CrawlerError> {
+    let started = Instant::now();
+    let hooks = &options.hooks;
+    let cancel = &options.cancel;
+    if cancel.is_cancelled() {
+        return Err(CrawlerError::Cancelled);
+    }
+
+    let mut sources = PageSources::new(options);
+    sources.japanese_titles |= user_attrib.title_language == TitleLanguage::Japanese;
+    if !options.ignore_robots {
+        let robots = options.transport.get_async(&robots_url(&sources.base_url), &[]).await;
+        sources.honor_robots(Robots::from_response(robots));
+    }
+
+    if let Some(auth) = &options.api {
+        match fetch_api_animelist(user_attrib, auth, &sources).await {
+            Ok(anime_list) => {
+                let anime_list = anime_list.into_iter().filter(|anime| hooks.entry_parsed(anime)).collect();
+                return Ok(finish_crawl(anime_list, ParseReport::new(), hooks, started));
+            },
+            Err(err) => warn!(%err, "could not read the list from the MAL API, scraping the list page"),
+        }
+    }
+
+    let url = animelist_url(&sources.base_url, user_attrib);
+    let body = check_list_page(user_attrib, sources.get_async(&url, &[]).await)?;
+    debug!(duration_ms = elapsed_ms(started), bytes = body.len(), "fetched list");
+    if cancel.is_cancelled() {
+        return Err(CrawlerError::Cancelled);
+    }
+    if options.transport.is_dry_run() {
+        return Ok(CrawlResult::default());
+    }
+
+    let mut payload = ListPayload::from_page(&body, user_attrib)?;
+    while let Some(url) = payload.next_page_url(&sources.base_url, user_attrib) {
+        if cancel.is_cancelled() {
+            return Err(CrawlerError::Cancelled);
+        }
+        payload.add_page(sources.get_async(&url, &[]).await);
+    }
+    let (anime_list, mut report) = parse_list_payload(payload, user_attrib, registered_words, hooks, sources.minutes_to_jst);
+
+    let checkpoint = open_checkpoint(user_attrib, options);
+    sources.checkpoint = checkpoint.clone();
+    let concurrency = std::cmp::min(sources.workers, sources.host_limit.max_per_host());
+    let permits = Arc::new(Semaphore::new(concurrency));
+    let sources = Arc::new(sources);
+
+    let mut result: Vec<Option<AnimeAttributes>> = Vec::with_capacity(anime_list.len());
+    let mut tasks = JoinSet::new();
+    for (index, anime) in anime_list.into_iter().enumerate() {
+        if !needs_anime_page(&anime, &sources) {
+            result.push(Some(anime));
+            continue;
+        }
+        result.push(None);
+        let permits = Arc::clone(&permits);
+        let sources = Arc::clone(&sources);
+        let cancel = cancel.clone();
+        tasks.spawn(async move {
+            // the semaphore is never closed.
+            let _permit = permits.acquire_owned().await.ok();
+            if cancel.is_cancelled() {
+                return (index, anime);
+            }
+            (index, update_precise_airing_day(anime, &sources).await)
+        });
+    }
+    let pages = tasks.len();
+    if let Some(progress) = &sources.progress {
+        progress.on_list_parsed(result.len(), pages);
+    }
+    let mut done = 0;
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, anime)) => result[index] = Some(anime),
+            Err(err) => warn!(%err, "anime page task failed"),
+        }
+        done += 1;
+        if let Some(progress) = &sources.progress {
+            progress.on_page_fetched(done, pages);
+        }
+    }
+    drop(sources);
+    // tasks that saw the cancellation gave their entry back untouched.
+    if cancel.is_cancelled() {
+        if !options.partial_results {
+            return Err(CrawlerError::Cancelled);
+        }
+        report.incomplete = Some(cancelled_crawl());
+    } else {
+        finish_checkpoint(checkpoint);
+    }
+
+    Ok(finish_crawl(result.into_iter().flatten().collect(), report, hooks, started))
+}
+
+/// Same as requester::get_animelist_with. Must be called from within a
+/// tokio runtime.
+pub async fn get_animelist(user_attrib: &UserAttributes, registered_words: &Trie,
+                           options: &CrawlOptions) -> Result<Vec<AnimeAttributes>, CrawlerError> {
+    crawl(user_attrib, registered_words, options).await.map(|result| result.entries)
+}
+
+
+#[test]
+fn test_async_replay_crawl() {
+    use crate::transport::Transport;
+
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let options = CrawlOptions { transport: Transport::Replay(fixtures), broadcast_times: true,
+                                 ..Default::default() };
+    let mut user = UserAttributes::new(String::from("fixture"));
+    user.set_date_format(String::from("1")).unwrap();
+    let registered_words = crate::config::REGISTERED_WORDS.to_vec();
+    let trie = Trie::new(Some(&registered_words));
+
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+    let anime_list = runtime.block_on(get_animelist(&user, &trie, &options)).unwrap();
+    let blocking = super::get_animelist_with(&user, &trie, &options).unwrap();
+    assert_eq!(anime_list.len(), 2);
+    assert_eq!(anime_list[0].title, "Sousou no Frieren");
+    assert_eq!(anime_list[0].airing_time, blocking[0].airing_time);
+    assert_eq!(anime_list[0].airing_weekday(), blocking[0].airing_weekday());
+}
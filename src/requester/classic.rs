@@ -0,0 +1,103 @@
+// Reads lists shown with MAL's classic template. Unlike the modern one, it
+// embeds no JSON: every entry is a table row, e.g.
+//
+//     <td class="header_cw">Currently Watching</td>
+//     ...
+//     <a href="/anime/52991/Sousou_no_Frieren" class="animetitle"><span>Sousou no Frieren</span></a>
+//     <span>Airing</span></td>
+//     <td ...><span class="score-label score-9">9</span></td>
+//     <td ...>TV</td>
+//     <td ...><span id="epText52991">7</span>/28</td>
+//
+// The rows are turned into the entries of a data-items payload, so that they
+// are parsed like those of the modern template. Rows show no start date, so
+// entries of classic lists only get their airing day from their anime page.
+
+use serde_json::{json, Value};
+
+use super::unescape_html;
+
+const TITLE_MARKER: &str = "class=\"animetitle\"";
+
+/// classes of the header rows starting a section of the list, with the
+/// number of the section.
+const SECTION_HEADERS: [(&str, i32); 5] = [("header_cw", 1), ("header_completed", 2), ("header_onhold", 3),
+                                           ("header_dropped", 4), ("header_ptw", 6)];
+
+/// the number of the section the last header of the markup starts, if any.
+fn last_section(html: &str) -> Option<i32> {
+    SECTION_HEADERS.iter().filter_map(|(class, status)| html.rfind(class).map(|at| (at, *status)))
+                   .max_by_key(|(at, _)| *at)
+                   .map(|(_, status)| status)
+}
+
+/// the text between the first `start` after `from` and the next `end`.
+fn between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let (_, rest) = text.split_once(start)?;
+    rest.split_once(end).map(|(value, _)| value)
+}
+
+/// a number of the row, 0 for "-" and what does not parse.
+fn number(text: Option<&str>) -> i32 {
+    text.and_then(|text| text.trim().parse::<i32>().ok()).unwrap_or(0)
+}
+
+/// the entries of the rows of a classic list page. Rows before the first
+/// section header belong to `status`, the section the page was asked for.
+pub fn list_items(body: &str, status: i32) -> Vec<Value> {
+    let mut items = Vec::new();
+    let mut section = status;
+    let mut rows = body.split(TITLE_MARKER);
+    let mut before = match rows.next() {
+        Some(before) => before,
+        None => return items,
+    };
+    for row in rows {
+        section = last_section(before).unwrap_or(section);
+        let anime_id = before.rsplit_once("href=\"/anime/")
+                             .map(|(_, link)| number(link.split(['/', '"']).next()))
+                             .unwrap_or(0);
+        let row_end = row.find("</tr>").unwrap_or(row.len());
+        let cells = &row[..row_end];
+        let title = between(cells, "<span>", "</span>").map(|title| unescape_html(title.trim())).unwrap_or_default();
+        let score = number(between(cells, "class=\"score-label", "</span>").and_then(|label| label.split_once('>'))
+                                                                            .map(|(_, score)| score));
+        let progress = format!("<span id=\"epText{}\">", anime_id);
+        let watched = number(between(cells, &progress, "</span>"));
+        let episodes = number(cells.split_once(&progress).and_then(|(_, rest)| between(rest, "</span>/", "<")));
+        let airing = between(cells, "</a>", "</td>").is_some_and(|after| after.contains(">Airing<"));
+        items.push(json!({
+            "status": section,
+            "score": score,
+            "num_watched_episodes": watched,
+            "anime_id": anime_id,
+            "anime_title": title,
+            "anime_num_episodes": episodes,
+            "anime_airing_status": if airing { 1 } else { 2 },
+        }));
+        before = row;
+    }
+    items
+}
+
+
+#[test]
+fn test_list_items() {
+    let page = "<table><tr><td class=\"header_cw\">Currently Watching</td></tr>\n\
+                <tr><td class=\"td1\"><a href=\"/anime/52991/Sousou_no_Frieren\" class=\"animetitle\">\
+                <span>Sousou no Frieren</span></a> <span style=\"font-size: 10px;\">Airing</span></td>\
+                <td class=\"td1\"><span class=\"score-label score-9\">9</span></td><td class=\"td1\">TV</td>\
+                <td class=\"td1\"><span id=\"epText52991\">7</span>/28</td></tr>\n\
+                <tr><td class=\"header_completed\">Completed</td></tr>\n\
+                <tr><td class=\"td1\"><a href=\"/anime/457/Mushishi\" class=\"animetitle\"><span>Mushi&#039;shi</span></a></td>\
+                <td class=\"td1\"><span class=\"score-label score-0\">-</span></td><td class=\"td1\">TV</td>\
+                <td class=\"td1\"><span id=\"epText457\">26</span>/26</td></tr></table>";
+    let items = list_items(page, 7);
+    assert_eq!(items, vec![
+        json!({"status": 1, "score": 9, "num_watched_episodes": 7, "anime_id": 52991,
+               "anime_title": "Sousou no Frieren", "anime_num_episodes": 28, "anime_airing_status": 1}),
+        json!({"status": 2, "score": 0, "num_watched_episodes": 26, "anime_id": 457,
+               "anime_title": "Mushi'shi", "anime_num_episodes": 26, "anime_airing_status": 2}),
+    ]);
+    assert!(list_items("<html></html>", 1).is_empty());
+}
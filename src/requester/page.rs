@@ -0,0 +1,57 @@
+// Parsers of anime pages. What a crawl reads from an anime page sits in the
+// information sidebar, one field per "spaceit_pad" block whose label is a
+// <span class="dark_text"> followed by the value, e.g.
+//
+//     <span class="dark_text">Broadcast:</span>
+//     Fridays at 23:00 (JST)
+//
+// A PageParser finds the value of such a field; the requester reads the
+// broadcast, titles and metadata out of those values. MarkupParser looks the
+// label up in the markup as it is, without building a tree of the page.
+
+/// Tag of the labels of the information sidebar, the label follows it.
+pub const LABEL_SELECTOR: &str = "<span class=\"dark_text\">";
+
+/// Finds the fields of the information sidebar of an anime page.
+pub trait PageParser: Send + Sync {
+    /// markup of the value of the field with the given label, e.g.
+    /// "Fridays at 23:00 (JST)" for "Broadcast:". None if the page has no
+    /// such field, an empty string if the field has no value.
+    fn info_field(&self, body: &str, label: &str) -> Option<String>;
+}
+
+/// Reads the fields out of the markup line by line: the value follows the
+/// closing tag of the label, on the same line or on the next one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkupParser;
+
+impl PageParser for MarkupParser {
+    fn info_field(&self, body: &str, label: &str) -> Option<String> {
+        let marker = format!("{}{}</span>", LABEL_SELECTOR, label);
+        let mut lines = body.lines();
+        while let Some(line) = lines.next() {
+            let rest = match line.split_once(marker.as_str()) {
+                Some((_, rest)) => rest.trim(),
+                None => continue,
+            };
+            let value = if rest.is_empty() { lines.next().unwrap_or("").trim() } else { rest };
+            return Some(value.trim_end_matches("</div>").trim().to_string());
+        }
+        None
+    }
+}
+
+
+#[test]
+fn test_markup_parser() {
+    let page = include_str!("../../fixtures/myanimelist.net_anime_52991.html");
+    assert_eq!(MarkupParser.info_field(page, "Broadcast:").as_deref(), Some("Fridays at 23:00 (JST)"));
+    assert_eq!(MarkupParser.info_field(page, "Japanese:").as_deref(), Some("葬送のフリーレン"));
+    assert_eq!(MarkupParser.info_field(page, "Premiered:"), None);
+
+    // only labels of the sidebar count, not the same text elsewhere.
+    let quoted = "<p>Broadcast:</span> Mondays at 12:00 (JST)</p>";
+    assert_eq!(MarkupParser.info_field(quoted, "Broadcast:"), None);
+    let empty = "<div><span class=\"dark_text\">Broadcast:</span></div>";
+    assert_eq!(MarkupParser.info_field(empty, "Broadcast:").as_deref(), Some(""));
+}
@@ -0,0 +1,91 @@
+// Scrapes the profile page of a user, e.g. myanimelist.net/profile/goksgie,
+// for what the list does not tell: when the user joined, their avatar, the
+// anime statistics MAL keeps for them and their favorite anime. The page
+// shows the statistics of the anime list before those of the manga list,
+// only the first are read.
+
+use chrono::NaiveDate;
+use tracing::{debug, instrument};
+
+use crate::error::CrawlerError;
+use super::{unescape_html, CrawlOptions, PageSources};
+
+const FAVORITES_MARKER: &str = "id=\"anime_favorites\"";
+
+/// What the crawler reads from the profile page of a user.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserProfile {
+    pub uname: String,
+    pub avatar_url: Option<String>,
+    pub joined: Option<NaiveDate>,
+    /// mean of the scores the user gave, None if they scored nothing.
+    pub mean_score: Option<f32>,
+    /// days spent watching anime, as MAL counts them.
+    pub days_watched: Option<f32>,
+    /// titles of the favorite anime, in the order of the page.
+    pub favorites: Vec<String>,
+}
+
+pub fn profile_url(base_url: &str, uname: &str) -> String {
+    format!("{}/profile/{}", base_url, uname)
+}
+
+/// the text between the first `start` and the next `end` after it.
+fn between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let (_, rest) = text.split_once(start)?;
+    rest.split_once(end).map(|(value, _)| value)
+}
+
+/// a number following its label in the statistics, e.g. "Days: </span>123.4".
+fn statistic(body: &str, label: &str) -> Option<f32> {
+    between(body, &format!("{}</span>", label), "<").and_then(|value| value.trim().parse::<f32>().ok())
+}
+
+/// parse a profile page, fields the page lacks are left empty.
+pub fn parse_profile_page(uname: &str, body: &str) -> UserProfile {
+    let avatar_url = body.split_once("class=\"user-image")
+                         .and_then(|(_, image)| between(image, "data-src=\"", "\""))
+                         .map(unescape_html);
+    let joined = between(body, ">Joined</span>", "</span>")
+                     .and_then(|joined| joined.rsplit('>').next())
+                     .and_then(|joined| NaiveDate::parse_from_str(joined.trim(), "%b %d, %Y").ok());
+    let favorites = between(body, FAVORITES_MARKER, "</ul>").map_or(Vec::new(), |favorites| {
+        favorites.split("<span class=\"title").skip(1)
+                 .filter_map(|favorite| between(favorite, ">", "</span>"))
+                 .map(|title| unescape_html(title.trim()))
+                 .collect()
+    });
+    UserProfile { uname: String::from(uname), avatar_url, joined,
+                  mean_score: statistic(body, "Mean Score: ").filter(|score| *score > 0.0),
+                  days_watched: statistic(body, "Days: "), favorites }
+}
+
+/// Fetches the profile page of the given user.
+#[instrument(skip(options))]
+pub fn get_user_profile(uname: &str, options: &CrawlOptions) -> Result<UserProfile, CrawlerError> {
+    let sources = PageSources::for_crawl(options);
+    let body = match sources.get(&profile_url(&sources.base_url, uname)) {
+        Err(CrawlerError::Status { status: 404, .. }) => return Err(CrawlerError::UserNotFound(String::from(uname))),
+        fetched => fetched?,
+    };
+    let profile = parse_profile_page(uname, &body);
+    debug!(favorites = profile.favorites.len(), "parsed profile page");
+    Ok(profile)
+}
+
+
+#[test]
+fn test_parse_profile_page() {
+    let page = include_str!("../../fixtures/myanimelist.net_profile_fixture.html");
+    let profile = parse_profile_page("fixture", page);
+    assert_eq!(profile.avatar_url.as_deref(),
+               Some("https://cdn.myanimelist.net/images/userimages/1234567.jpg?t=1697000000"));
+    assert_eq!(profile.joined, NaiveDate::from_ymd_opt(2015, 1, 5));
+    assert_eq!(profile.days_watched, Some(123.4));
+    assert_eq!(profile.mean_score, Some(7.95));
+    assert_eq!(profile.favorites, vec!["Mushishi", "Sousou no Frieren"]);
+
+    let empty = parse_profile_page("nobody", "<html></html>");
+    assert_eq!(empty, UserProfile { uname: String::from("nobody"), ..Default::default() });
+    assert_eq!(profile_url(crate::config::MAL_URL, "goksgie"), "https://myanimelist.net/profile/goksgie");
+}
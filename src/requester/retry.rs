@@ -0,0 +1,86 @@
+// Retries of requests MAL turned away for the moment: 429 when it rate
+// limits the crawler, 5xx while it is overloaded, and timeouts. Attempts are
+// spaced out exponentially, with jitter so that the workers of a crawl do
+// not retry in lockstep. A Retry-After of the response wins over the
+// backoff.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::error::CrawlerError;
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// attempts of a request including the first one, 1 disables retries.
+    pub max_attempts: u32,
+    /// delay before the first retry, doubled for every further one.
+    pub base_delay: Duration,
+    /// the delay never grows beyond this.
+    pub max_delay: Duration,
+    /// wait a random time between half of the delay and the full delay.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy { max_attempts: 4, base_delay: Duration::from_millis(500),
+                      max_delay: Duration::from_secs(30), jitter: true }
+    }
+}
+
+/// a random number, without pulling in a random number generator for it.
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+impl RetryPolicy {
+    /// send every request once.
+    pub fn never() -> Self {
+        RetryPolicy { max_attempts: 1, ..Default::default() }
+    }
+
+    /// how long to wait before the given retry, starting at 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        if !self.jitter || delay.is_zero() {
+            return delay;
+        }
+        let half = delay / 2;
+        half + Duration::from_nanos(random() % (half.as_nanos() as u64 + 1))
+    }
+
+    /// how long to wait before retrying the request that failed with `err`
+    /// on the given attempt, None if it should not be retried.
+    pub fn retry_after(&self, err: &CrawlerError, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts || !err.is_transient() {
+            return None;
+        }
+        Some(err.retry_after().unwrap_or_else(|| self.delay(attempt)))
+    }
+}
+
+
+#[test]
+fn test_retry_policy() {
+    let policy = RetryPolicy { jitter: false, ..Default::default() };
+    assert_eq!(policy.delay(1), Duration::from_millis(500));
+    assert_eq!(policy.delay(3), Duration::from_secs(2));
+    assert_eq!(policy.delay(20), Duration::from_secs(30));
+
+    let jittered = RetryPolicy::default().delay(2);
+    assert!(jittered >= Duration::from_millis(500) && jittered <= Duration::from_secs(1));
+
+    let busy = CrawlerError::Status { url: String::from("https://myanimelist.net/anime/1/"), status: 503,
+                                     retry_after: None };
+    let limited = CrawlerError::Status { url: String::from("https://myanimelist.net/anime/1/"), status: 429,
+                                        retry_after: Some(Duration::from_secs(7)) };
+    let missing = CrawlerError::Status { url: String::from("https://myanimelist.net/anime/1/"), status: 404,
+                                        retry_after: None };
+    assert_eq!(policy.retry_after(&busy, 1), Some(Duration::from_millis(500)));
+    assert_eq!(policy.retry_after(&limited, 2), Some(Duration::from_secs(7)));
+    assert_eq!(policy.retry_after(&busy, 4), None);
+    assert_eq!(policy.retry_after(&missing, 1), None);
+    assert_eq!(RetryPolicy::never().retry_after(&busy, 1), None);
+}
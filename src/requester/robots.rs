@@ -0,0 +1,187 @@
+// robots.txt of myanimelist.net, so that crawls stay away from disallowed
+// paths and space out their requests by the crawl delay the site asks for.
+//
+// Only the subset MAL uses is supported: user-agent groups, allow/disallow
+// rules with `*` wildcards and `$` anchors, and crawl-delay.
+
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::error::CrawlerError;
+use crate::transport::Transport;
+
+/// url of robots.txt of the host at the base url, e.g. MAL_URL.
+pub fn robots_url(base_url: &str) -> String {
+    format!("{}/robots.txt", base_url)
+}
+
+// user agent the rules are looked up for, before falling back to `*`. The
+// product token of config::USER_AGENT.
+const ROBOTS_AGENT: &str = "anime-crawler";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+#[derive(Debug, Default)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+/// Rules of robots.txt that apply to the crawler. The default allows
+/// everything, which is also what a missing robots.txt means.
+#[derive(Debug, Clone, Default)]
+pub struct Robots {
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+/// true if the robots.txt path pattern matches the path.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut pieces = pattern.split('*');
+    let first = pieces.next().unwrap_or("");
+    if !path.starts_with(first) {
+        return false;
+    }
+
+    let mut pos = first.len();
+    let pieces: Vec<&str> = pieces.collect();
+    for (index, piece) in pieces.iter().enumerate() {
+        if anchored && index + 1 == pieces.len() {
+            return path[pos..].ends_with(piece);
+        }
+        match path[pos..].find(piece) {
+            Some(found) => pos += found + piece.len(),
+            None => return false,
+        }
+    }
+    !anchored || pos == path.len()
+}
+
+impl Robots {
+    pub fn parse(body: &str) -> Self {
+        let mut groups: Vec<Group> = Vec::new();
+        let mut current = Group::default();
+        let mut in_rules = false;
+
+        for line in body.lines() {
+            let line = line.split('#').next().unwrap_or("");
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim().to_lowercase(), value.trim()),
+                None => continue,
+            };
+            match key.as_str() {
+                "user-agent" => {
+                    // a user-agent line after rules starts a new group.
+                    if in_rules {
+                        groups.push(std::mem::take(&mut current));
+                        in_rules = false;
+                    }
+                    current.agents.push(value.to_lowercase());
+                },
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // an empty disallow allows everything.
+                    if !value.is_empty() {
+                        current.rules.push(Rule { allow: key == "allow", pattern: String::from(value) });
+                    }
+                },
+                "crawl-delay" => {
+                    in_rules = true;
+                    current.crawl_delay = value.parse::<f64>().ok()
+                                               .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                                               .map(Duration::from_secs_f64);
+                },
+                _ => {}
+            }
+        }
+        groups.push(current);
+
+        let ours = |group: &&Group| group.agents.iter().any(|agent| agent == ROBOTS_AGENT);
+        let wildcard = |group: &&Group| group.agents.iter().any(|agent| agent == "*");
+        let selected: Vec<&Group> = if groups.iter().any(|group| ours(&group)) {
+            groups.iter().filter(ours).collect()
+        } else {
+            groups.iter().filter(wildcard).collect()
+        };
+
+        Robots { rules: selected.iter().flat_map(|group| group.rules.iter().cloned()).collect(),
+                 crawl_delay: selected.iter().filter_map(|group| group.crawl_delay).max() }
+    }
+
+    /// fetch robots.txt of the host at the base url, a robots.txt that
+    /// cannot be fetched allows everything.
+    pub fn fetch(transport: &Transport, base_url: &str) -> Self {
+        Robots::from_response(transport.get(&robots_url(base_url)))
+    }
+
+    /// same as fetch, given the response to a request for robots.txt.
+    pub fn from_response(response: Result<String, CrawlerError>) -> Self {
+        match response {
+            Ok(body) => Robots::parse(&body),
+            Err(err) => {
+                warn!(%err, "could not fetch robots.txt, assuming everything is allowed");
+                Robots::default()
+            }
+        }
+    }
+
+    /// true if the path (with its query) may be crawled. The longest
+    /// matching rule wins, allow wins a tie.
+    pub fn allows(&self, path: &str) -> bool {
+        self.rules.iter()
+                  .filter(|rule| pattern_matches(&rule.pattern, path))
+                  .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+                  .is_none_or(|rule| rule.allow)
+    }
+
+    /// same as allows, for a full url.
+    pub fn check_url(&self, url: &str) -> Result<(), CrawlerError> {
+        let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let path = without_scheme.find('/').map_or("/", |pos| &without_scheme[pos..]);
+        if self.allows(path) {
+            Ok(())
+        } else {
+            Err(CrawlerError::Disallowed(String::from(url)))
+        }
+    }
+
+    pub fn crawl_delay(&self) -> Option<Duration> {
+        self.crawl_delay
+    }
+}
+
+
+#[test]
+fn test_robots_rules() {
+    let robots = Robots::parse("# comment\n\
+                                User-agent: Googlebot\n\
+                                Disallow: /\n\
+                                \n\
+                                User-agent: *\n\
+                                Disallow: /login.php\n\
+                                Disallow: /animelist/*?status=7\n\
+                                Disallow: /anime/*/pics$\n\
+                                Allow: /login.php?help\n\
+                                Crawl-delay: 1.5\n");
+    assert!(robots.allows("/animelist/goksgie?status=1"));
+    assert!(!robots.allows("/animelist/goksgie?status=7"));
+    assert!(!robots.allows("/login.php"));
+    assert!(robots.allows("/login.php?help"));
+    assert!(!robots.allows("/anime/5081/pics"));
+    assert!(robots.allows("/anime/5081/pics/1"));
+    assert_eq!(robots.crawl_delay(), Some(Duration::from_millis(1500)));
+    assert!(robots.check_url("https://myanimelist.net/login.php").is_err());
+
+    let ours = Robots::parse("User-agent: *\nDisallow: /\n\nUser-agent: anime-crawler\nDisallow:\n");
+    assert!(ours.allows("/anime/5081/"));
+}
@@ -0,0 +1,160 @@
+// Crawls the seasonal page of MAL, e.g. myanimelist.net/anime/season/2023/fall,
+// which lists every anime starting in a season whatever the lists of users.
+// Like the list page it is scraped from its markup: every anime is a
+// "seasonal-anime" block carrying its id, title, start date, genres, studios
+// and community score.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, Local, NaiveDate, Weekday};
+use tracing::{debug, instrument, warn};
+
+use crate::error::CrawlerError;
+use super::{link_texts, unescape_html, CrawlOptions, PageSources};
+
+const ENTRY_MARKER: &str = "class=\"js-anime-category-producer seasonal-anime";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Winter,
+    Spring,
+    Summer,
+    Fall,
+}
+
+impl Season {
+    /// the season anime starting in the given month (1 to 12) belong to.
+    pub fn of_month(month: u32) -> Self {
+        match month {
+            1..=3 => Season::Winter,
+            4..=6 => Season::Spring,
+            7..=9 => Season::Summer,
+            _ => Season::Fall,
+        }
+    }
+
+    /// year and season of the local date.
+    pub fn current() -> (i32, Self) {
+        let today = Local::now().date_naive();
+        (today.year(), Season::of_month(today.month()))
+    }
+
+    /// name of the season in MAL's urls.
+    pub fn name(self) -> &'static str {
+        match self {
+            Season::Winter => "winter",
+            Season::Spring => "spring",
+            Season::Summer => "summer",
+            Season::Fall => "fall",
+        }
+    }
+}
+
+impl fmt::Display for Season {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Season {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "winter" => Ok(Season::Winter),
+            "spring" => Ok(Season::Spring),
+            "summer" => Ok(Season::Summer),
+            "fall" | "autumn" => Ok(Season::Fall),
+            other => Err(format!("unknown season {}, expected winter, spring, summer or fall", other)),
+        }
+    }
+}
+
+/// An anime of the seasonal page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeasonalAnime {
+    pub id: i32,
+    pub title: String,
+    pub genres: Vec<String>,
+    pub studios: Vec<String>,
+    /// community score, None while too few users scored it.
+    pub score: Option<f32>,
+    pub start_date: Option<NaiveDate>,
+}
+
+impl SeasonalAnime {
+    /// weekday the anime airs on in JST, the weekday of its start date.
+    pub fn airing_weekday(&self) -> Option<Weekday> {
+        self.start_date.map(|date| date.weekday())
+    }
+}
+
+pub fn season_url(base_url: &str, year: i32, season: Season) -> String {
+    format!("{}/anime/season/{}/{}", base_url, year, season)
+}
+
+/// the text between the first `start` and the next `end` after it.
+fn between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let (_, rest) = text.split_once(start)?;
+    rest.split_once(end).map(|(value, _)| value)
+}
+
+/// parse a block of the seasonal page, None if it has no id or title.
+fn parse_entry(block: &str) -> Option<SeasonalAnime> {
+    let id = between(block, "data-id=\"", "\"")?.parse::<i32>().ok()?;
+    let title = unescape_html(between(block, "class=\"link-title\">", "</a>")?.trim());
+    let genres = block.split("<span class=\"genre\">").skip(1)
+                      .filter_map(|genre| link_texts(genre).into_iter().next())
+                      .collect();
+    // "Studio" or "Studios", followed by the links to them.
+    let studios = between(block, "<span class=\"caption\">Studio", "</div>").map_or(Vec::new(), link_texts);
+    let score = between(block, "score-label", "</div>")
+                    .and_then(|score| score.rsplit('>').next())
+                    .and_then(|score| score.trim().parse::<f32>().ok());
+    let start_date = between(block, "<div class=\"info\"><span class=\"item\">", "</span>")
+                         .and_then(|date| NaiveDate::parse_from_str(date.trim(), "%b %d, %Y").ok());
+    Some(SeasonalAnime { id, title, genres, studios, score, start_date })
+}
+
+/// the anime of a seasonal page, blocks that cannot be parsed are skipped.
+pub fn parse_season_page(body: &str) -> Vec<SeasonalAnime> {
+    let mut entries = Vec::new();
+    for block in body.split(ENTRY_MARKER).skip(1) {
+        match parse_entry(block) {
+            Some(entry) => entries.push(entry),
+            None => warn!("skipping malformed seasonal entry"),
+        }
+    }
+    entries
+}
+
+/// Crawls the anime of the given season.
+#[instrument(skip(options))]
+pub fn get_seasonal(year: i32, season: Season, options: &CrawlOptions) -> Result<Vec<SeasonalAnime>, CrawlerError> {
+    let sources = PageSources::for_crawl(options);
+    let body = sources.get(&season_url(&sources.base_url, year, season))?;
+    let entries = parse_season_page(&body);
+    debug!(entries = entries.len(), "parsed seasonal page");
+    Ok(entries)
+}
+
+
+#[test]
+fn test_parse_season_page() {
+    let page = include_str!("../../fixtures/myanimelist.net_anime_season_2023_fall.html");
+    let entries = parse_season_page(page);
+    assert_eq!(entries.len(), 3);
+    assert_eq!((entries[0].id, entries[0].title.as_str()), (52991, "Sousou no Frieren"));
+    assert_eq!(entries[0].genres, vec!["Adventure", "Drama", "Fantasy"]);
+    assert_eq!(entries[0].studios, vec!["Madhouse"]);
+    assert_eq!(entries[0].score, Some(9.1));
+    assert_eq!(entries[0].airing_weekday(), Some(Weekday::Fri));
+    assert_eq!(entries[1].studios, vec!["Wit Studio", "CloverWorks"]);
+    assert_eq!(entries[2].score, None);
+    assert_eq!(entries[2].start_date, NaiveDate::from_ymd_opt(2023, 10, 22));
+
+    assert_eq!(season_url(crate::config::MAL_URL, 2023, Season::of_month(11)),
+               "https://myanimelist.net/anime/season/2023/fall");
+    assert_eq!("autumn".parse::<Season>(), Ok(Season::Fall));
+}
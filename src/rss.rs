@@ -0,0 +1,101 @@
+// Polls a torrent RSS feed (nyaa by default) and matches its items to
+// airing entries, so the report can link releases of freshly aired episodes.
+//
+// Item titles are release names, so they go through the same parser as the
+// local downloads folder.
+
+use crate::anime::AnimeAttributes;
+use crate::local_files::parse_filename;
+use crate::titles::normalize_title;
+use crate::error::CrawlerError;
+use crate::transport::Transport;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedItem {
+    pub title: String,
+    pub link: String,
+}
+
+fn unescape_xml(value: &str) -> String {
+    let value = value.trim();
+    let value = value.strip_prefix("<![CDATA[")
+                     .and_then(|v| v.strip_suffix("]]>"))
+                     .unwrap_or(value);
+    value.replace("&lt;", "<")
+         .replace("&gt;", ">")
+         .replace("&quot;", "\"")
+         .replace("&#39;", "'")
+         .replace("&apos;", "'")
+         .replace("&amp;", "&")
+}
+
+/// return the text between <tag> and </tag> in the given fragment.
+fn tag_content<'a>(fragment: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = fragment.find(&open)? + open.len();
+    let end = fragment[start..].find(&close)? + start;
+    Some(&fragment[start..end])
+}
+
+/// Naive RSS parser: we only need the title and link of every item, so the
+/// feed is split on <item> rather than parsed as a whole document.
+pub fn parse_feed(body: &str) -> Vec<FeedItem> {
+    body.split("<item>")
+        .skip(1)
+        .filter_map(|item| {
+            let title = tag_content(item, "title")?;
+            let link = tag_content(item, "link")?;
+            Some(FeedItem { title: unescape_xml(title), link: unescape_xml(link) })
+        })
+        .collect()
+}
+
+pub fn fetch_feed(transport: &Transport, url: &str) -> Result<Vec<FeedItem>, CrawlerError> {
+    let body = transport.get(url)?;
+    Ok(parse_feed(&body))
+}
+
+/// Returns the releases of the latest episode of the given entry found in
+/// the feed, together with that episode number.
+pub fn latest_releases<'a>(anime: &AnimeAttributes, items: &'a [FeedItem]) -> Option<(i32, Vec<&'a FeedItem>)> {
+    let titles: Vec<String> = anime.titles().map(normalize_title).collect();
+    let matched: Vec<(i32, &FeedItem)> = items.iter()
+        .filter_map(|item| {
+            let (title, episode) = parse_filename(&item.title)?;
+            if titles.contains(&normalize_title(&title)) {
+                Some((episode, item))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let latest = matched.iter().map(|(episode, _)| *episode).max()?;
+    let releases = matched.into_iter()
+                          .filter(|(episode, _)| *episode == latest)
+                          .map(|(_, item)| item)
+                          .collect();
+    Some((latest, releases))
+}
+
+
+#[test]
+fn test_parse_feed() {
+    let body = "<rss><channel><title>Nyaa</title>\
+                <item><title>[SubsPlease] Dungeon Meshi - 12 (1080p) [0A1B2C3D].mkv</title>\
+                <link>https://nyaa.si/download/1.torrent</link></item>\
+                <item><title><![CDATA[[Erai-raws] Kusuriya no Hitorigoto - 03 [720p].mkv]]></title>\
+                <link>https://nyaa.si/download/2.torrent?a=1&amp;b=2</link></item>\
+                </channel></rss>";
+    let items = parse_feed(body);
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[1].title, "[Erai-raws] Kusuriya no Hitorigoto - 03 [720p].mkv");
+    assert_eq!(items[1].link, "https://nyaa.si/download/2.torrent?a=1&b=2");
+
+    let mut anime = AnimeAttributes::new();
+    anime.title = String::from("Dungeon Meshi");
+    let (episode, releases) = latest_releases(&anime, &items).unwrap();
+    assert_eq!(episode, 12);
+    assert_eq!(releases, vec![&items[0]]);
+}
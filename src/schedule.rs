@@ -0,0 +1,122 @@
+// The airing entries of a list grouped by the weekday they air on.
+
+use chrono::{Datelike, NaiveDateTime, Weekday};
+
+use crate::anime::AnimeAttributes;
+
+const WEEK: [Weekday; 7] = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu,
+                            Weekday::Fri, Weekday::Sat, Weekday::Sun];
+
+#[derive(Debug, Clone, Default)]
+pub struct WeeklySchedule {
+    days: [Vec<AnimeAttributes>; 7],
+}
+
+impl WeeklySchedule {
+    /// group the airing entries by weekday, finished entries are left out.
+    pub fn from_entries<I: IntoIterator<Item = AnimeAttributes>>(entries: I) -> Self {
+        let mut schedule = WeeklySchedule::default();
+        for anime in entries {
+            if let Some(weekday) = anime.airing_weekday() {
+                schedule.days[weekday.num_days_from_monday() as usize].push(anime);
+            }
+        }
+        schedule
+    }
+
+    /// entries airing on the given weekday.
+    pub fn on(&self, weekday: Weekday) -> &[AnimeAttributes] {
+        &self.days[weekday.num_days_from_monday() as usize]
+    }
+
+    /// every weekday, starting from monday, with its entries.
+    pub fn iter(&self) -> impl Iterator<Item = (Weekday, &[AnimeAttributes])> {
+        WEEK.iter().map(move |weekday| (*weekday, self.on(*weekday)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.days.iter().map(|day| day.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// format the time left until an episode airs, e.g. "2h 41m" or "3d 4h".
+pub fn format_countdown(left: chrono::Duration) -> String {
+    let minutes = left.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// what to expect of the entry next at the local time `now`, e.g. "Episode
+/// 7 airs in 5h 12m", or "Episode 7 airs on Fri" if the airing time is not
+/// known. Empty for entries that are not airing.
+pub fn describe_next_episode(anime: &AnimeAttributes, now: NaiveDateTime) -> String {
+    let weekday = match anime.airing_weekday() {
+        Some(weekday) => weekday,
+        None => return String::new(),
+    };
+    if anime.episodes_remaining(now) == Some(0) {
+        return String::from("every episode aired");
+    }
+    let episode = anime.next_episode_number(now)
+                       .map_or_else(|| String::from("Next episode"), |number| format!("Episode {}", number));
+    match anime.next_episode_at(now) {
+        Some(next) => format!("{} airs in {}", episode, format_countdown(next.naive_local() - now)),
+        None if weekday == now.weekday() => format!("{} airs today", episode),
+        None => format!("{} airs on {}", episode, weekday),
+    }
+}
+
+
+#[test]
+fn test_weekly_schedule() {
+    let mut airing = AnimeAttributes::new();
+    airing.is_airing = true;
+    airing.update_airing_day(5);
+    let mut late_night = airing.clone();
+    late_night.update_airing_day(3);
+    let finished = AnimeAttributes::new();
+
+    let schedule = WeeklySchedule::from_entries(vec![airing, late_night, finished]);
+    assert_eq!(schedule.len(), 2);
+    assert_eq!(schedule.on(Weekday::Fri).len(), 1);
+    // shifted past sunday, so it wraps around to monday.
+    assert_eq!(schedule.on(Weekday::Mon).len(), 1);
+    assert_eq!(schedule.iter().next().map(|(weekday, _)| weekday), Some(Weekday::Mon));
+}
+
+#[test]
+fn test_format_countdown() {
+    assert_eq!(format_countdown(chrono::Duration::minutes(161)), "2h 41m");
+    assert_eq!(format_countdown(chrono::Duration::hours(76)), "3d 4h");
+    assert_eq!(format_countdown(chrono::Duration::seconds(59)), "0m");
+}
+
+#[test]
+fn test_describe_next_episode() {
+    use chrono::{NaiveDate, NaiveTime};
+
+    let monday = NaiveDate::from_ymd_opt(2023, 11, 6).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    let mut anime = AnimeAttributes::new();
+    assert_eq!(describe_next_episode(&anime, monday), "");
+
+    anime.is_airing = true;
+    anime.set_airing_weekday(Weekday::Mon);
+    assert_eq!(describe_next_episode(&anime, monday), "Next episode airs today");
+    anime.set_airing_weekday(Weekday::Fri);
+    anime.first_aired = NaiveDate::from_ymd_opt(2023, 9, 29);
+    assert_eq!(describe_next_episode(&anime, monday), "Episode 7 airs on Fri");
+    anime.airing_time = NaiveTime::from_hms_opt(17, 12, 0);
+    assert_eq!(describe_next_episode(&anime, monday), "Episode 7 airs in 4d 5h");
+    anime.num_episodes = 6;
+    assert_eq!(describe_next_episode(&anime, monday), "every episode aired");
+}
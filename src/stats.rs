@@ -0,0 +1,118 @@
+// Statistics of a whole list, as the stats command prints them: how many
+// entries each section holds, the scores the user gave, and the episodes
+// and time they watched. The time is the watched episodes of every entry
+// times the length of its episodes, which only the anime pages and the API
+// report; entries of unknown length are counted apart.
+
+use crate::anime::{AnimeAttributes, ListStatus};
+
+/// sections of a list in the order MAL shows them.
+const SECTIONS: [ListStatus; 5] = [ListStatus::Watching, ListStatus::Completed, ListStatus::OnHold,
+                                   ListStatus::Dropped, ListStatus::PlanToWatch];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListStats {
+    pub entries: usize,
+    /// number of entries of every section, in the order of the list page.
+    pub per_status: Vec<(ListStatus, usize)>,
+    /// mean of the scores the user gave, unscored entries left out.
+    pub mean_score: Option<f32>,
+    /// number of entries scored 1 to 10, at index score - 1.
+    pub score_counts: [usize; 10],
+    pub episodes_watched: i64,
+    /// time spent on the episodes of known length, in seconds.
+    pub watch_seconds: u64,
+    /// entries with watched episodes of unknown length.
+    pub unknown_length: usize,
+}
+
+impl ListStats {
+    pub fn from_entries(entries: &[AnimeAttributes]) -> Self {
+        let mut stats = ListStats { entries: entries.len(), ..Default::default() };
+        stats.per_status = SECTIONS.iter().map(|section| {
+            (*section, entries.iter().filter(|anime| anime.status == section.number()).count())
+        }).collect();
+
+        for anime in entries {
+            if (1..=10).contains(&anime.score) {
+                stats.score_counts[anime.score as usize - 1] += 1;
+            }
+            let watched = anime.num_watched_episodes.max(0);
+            stats.episodes_watched += watched as i64;
+            match anime.episode_seconds {
+                Some(seconds) => stats.watch_seconds += watched as u64 * seconds as u64,
+                None if watched > 0 => stats.unknown_length += 1,
+                None => {},
+            }
+        }
+
+        let scored: usize = stats.score_counts.iter().sum();
+        let total: usize = stats.score_counts.iter().enumerate().map(|(index, count)| (index + 1) * count).sum();
+        stats.mean_score = Some(scored).filter(|scored| *scored > 0).map(|scored| total as f32 / scored as f32);
+        stats
+    }
+
+    /// one line per score from 10 down to 1, with a bar as long as its
+    /// share of the scored entries, the most common score taking `width`
+    /// columns.
+    pub fn histogram(&self, width: usize) -> Vec<String> {
+        let most = self.score_counts.iter().copied().max().unwrap_or(0);
+        (1..=10).rev().map(|score| {
+            let count = self.score_counts[score - 1];
+            if count == 0 {
+                return format!("{:>2} |", score);
+            }
+            let bar = (count * width).checked_div(most).unwrap_or(0);
+            format!("{:>2} | {} {}", score, "█".repeat(bar), count)
+        }).collect()
+    }
+}
+
+/// the time as days, hours and minutes, e.g. "2 days 5 hours 12 minutes".
+/// Units that are zero are left out.
+pub fn format_watch_time(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    let parts = [(minutes / (24 * 60), "day"), (minutes / 60 % 24, "hour"), (minutes % 60, "minute")];
+    let text: Vec<String> = parts.iter().filter(|(amount, _)| *amount > 0)
+                                 .map(|(amount, unit)| format!("{} {}{}", amount, unit, if *amount == 1 { "" } else { "s" }))
+                                 .collect();
+    if text.is_empty() { String::from("0 minutes") } else { text.join(" ") }
+}
+
+
+#[test]
+fn test_list_stats() {
+    let mut frieren = AnimeAttributes::new();
+    frieren.status = ListStatus::Watching.number();
+    frieren.score = 9;
+    frieren.num_watched_episodes = 12;
+    frieren.episode_seconds = Some(24 * 60);
+    let mut mushishi = AnimeAttributes::new();
+    mushishi.status = ListStatus::Completed.number();
+    mushishi.score = 10;
+    mushishi.num_watched_episodes = 26;
+    let mut kaiju = AnimeAttributes::new();
+    kaiju.status = ListStatus::PlanToWatch.number();
+
+    let stats = ListStats::from_entries(&[frieren, mushishi, kaiju]);
+    assert_eq!(stats.entries, 3);
+    assert_eq!(stats.per_status, vec![(ListStatus::Watching, 1), (ListStatus::Completed, 1), (ListStatus::OnHold, 0),
+                                      (ListStatus::Dropped, 0), (ListStatus::PlanToWatch, 1)]);
+    assert_eq!(stats.mean_score, Some(9.5));
+    assert_eq!(stats.episodes_watched, 38);
+    assert_eq!(stats.watch_seconds, 12 * 24 * 60);
+    assert_eq!(stats.unknown_length, 1);
+
+    let histogram = stats.histogram(4);
+    assert_eq!(histogram.len(), 10);
+    assert_eq!(histogram[0], "10 | ████ 1");
+    assert_eq!(histogram[9], " 1 |");
+    assert_eq!(ListStats::from_entries(&[]).mean_score, None);
+}
+
+#[test]
+fn test_format_watch_time() {
+    assert_eq!(format_watch_time(12 * 24 * 60), "4 hours 48 minutes");
+    assert_eq!(format_watch_time(2 * 86400 + 3600 + 60), "2 days 1 hour 1 minute");
+    assert_eq!(format_watch_time(30), "0 minutes");
+}
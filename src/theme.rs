@@ -0,0 +1,124 @@
+// Colors of the text output. A theme assigns a color to each role of the
+// output (column headers, titles, entries airing today, finished entries and
+// times), and is applied alike to the table, the live countdowns and the
+// exported schedule.
+//
+// Themes are picked by name ("default", "colorblind", "plain") and their
+// colors can be overridden one role at a time with a "role=#rrggbb" list,
+// e.g. MAL_THEME_COLORS="airing=#e69f00,time=#f0e442".
+
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl FromStr for Rgb {
+    type Err = String;
+
+    /// parse a "#rrggbb" color.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let hex = value.strip_prefix('#').filter(|hex| hex.len() == 6 && hex.is_ascii())
+                       .ok_or_else(|| format!("invalid color {}, expected #rrggbb", value))?;
+        let channel = |range: std::ops::Range<usize>| {
+            u8::from_str_radix(&hex[range], 16).map_err(|_| format!("invalid color {}, expected #rrggbb", value))
+        };
+        Ok(Rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?))
+    }
+}
+
+impl Rgb {
+    /// "#rrggbb" form of the color, as used by SVG.
+    pub fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+/// Color of each role, None leaves the text in the default color.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Theme {
+    pub header: Option<Rgb>,
+    pub title: Option<Rgb>,
+    pub airing: Option<Rgb>,
+    pub finished: Option<Rgb>,
+    pub time: Option<Rgb>,
+}
+
+pub const THEME_NAMES: [&str; 3] = ["default", "colorblind", "plain"];
+
+impl Theme {
+    /// the theme with the given name, see THEME_NAMES.
+    pub fn named(name: &str) -> Option<Theme> {
+        let theme = match name {
+            "default" => Theme { header: Some(Rgb(0x9e, 0xcb, 0xff)), title: None,
+                                 airing: Some(Rgb(0x8a, 0xe2, 0x34)), finished: Some(Rgb(0x88, 0x88, 0x88)),
+                                 time: Some(Rgb(0xf0, 0xc6, 0x74)) },
+            // Okabe-Ito colors, told apart with any kind of color vision
+            // deficiency.
+            "colorblind" => Theme { header: Some(Rgb(0x56, 0xb4, 0xe9)), title: None,
+                                    airing: Some(Rgb(0xe6, 0x9f, 0x00)), finished: Some(Rgb(0x99, 0x99, 0x99)),
+                                    time: Some(Rgb(0xf0, 0xe4, 0x42)) },
+            "plain" => Theme::default(),
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    /// override colors of the theme from a "role=#rrggbb,role=#rrggbb"
+    /// list, "role=none" resets a role to the default color.
+    pub fn apply_overrides(&mut self, overrides: &str) -> Result<(), String> {
+        for assignment in overrides.split(',').map(str::trim).filter(|assignment| !assignment.is_empty()) {
+            let (role, color) = assignment.split_once('=')
+                                          .ok_or_else(|| format!("invalid color assignment {}, expected role=#rrggbb", assignment))?;
+            let color = match color.trim() {
+                "none" => None,
+                color => Some(color.parse::<Rgb>()?),
+            };
+            match role.trim() {
+                "header" => self.header = color,
+                "title" => self.title = color,
+                "airing" => self.airing = color,
+                "finished" => self.finished = color,
+                "time" => self.time = color,
+                other => return Err(format!("unknown theme role {}, expected header, title, airing, finished or time",
+                                            other)),
+            }
+        }
+        Ok(())
+    }
+
+    /// the named theme with the overrides applied.
+    pub fn load(name: &str, overrides: Option<&str>) -> Result<Theme, String> {
+        let mut theme = Theme::named(name).ok_or_else(|| {
+            format!("unknown theme {}, expected one of {}", name, THEME_NAMES.join(", "))
+        })?;
+        if let Some(overrides) = overrides {
+            theme.apply_overrides(overrides)?;
+        }
+        Ok(theme)
+    }
+}
+
+/// the text in the given color for a true color terminal, as is without one.
+pub fn paint(color: Option<Rgb>, text: &str) -> String {
+    match color {
+        Some(Rgb(r, g, b)) => format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, text),
+        None => String::from(text),
+    }
+}
+
+
+#[test]
+fn test_theme_overrides() {
+    let mut theme = Theme::named("colorblind").unwrap();
+    theme.apply_overrides("airing=#FF0000, finished=none").unwrap();
+    assert_eq!(theme.airing, Some(Rgb(0xff, 0, 0)));
+    assert_eq!(theme.finished, None);
+    assert_eq!(theme.airing.unwrap().hex(), "#ff0000");
+
+    assert!(theme.apply_overrides("airing=red").is_err());
+    assert!(theme.apply_overrides("background=#000000").is_err());
+    assert!(Theme::load("solarized", None).is_err());
+
+    assert_eq!(paint(None, "Frieren"), "Frieren");
+    assert_eq!(paint(Some(Rgb(1, 2, 3)), "Frieren"), "\x1b[38;2;1;2;3mFrieren\x1b[0m");
+}
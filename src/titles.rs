@@ -0,0 +1,231 @@
+// Title matching across the romaji title, the English title, the Japanese
+// title and the synonyms of an entry. Titles are compared in a normalized
+// form, so that case, punctuation and the many ways of numbering a season
+// ("Season 2", "2nd Season", "S2", "II") do not get in the way.
+//
+// Searches match loosely instead: the letters of the query have to appear in
+// the title in order, but not next to each other, so "frieren" finds
+// "Sousou no Frieren" and "sxf" finds "Spy x Family". Matches on word starts
+// and runs of letters rank higher.
+//
+// Japanese titles take two terminal columns per character, so text output
+// is aligned by display width rather than by the number of characters.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::anime::AnimeAttributes;
+
+/// Language titles are shown in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TitleLanguage {
+    #[default]
+    Romaji,
+    English,
+    Japanese,
+}
+
+impl FromStr for TitleLanguage {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "romaji" => Ok(TitleLanguage::Romaji),
+            "english" => Ok(TitleLanguage::English),
+            "japanese" => Ok(TitleLanguage::Japanese),
+            other => Err(format!("unknown title language {}, expected romaji, english or japanese", other)),
+        }
+    }
+}
+
+/// true for characters a terminal draws two columns wide: CJK ideographs,
+/// kana, hangul and fullwidth forms.
+fn is_wide(c: char) -> bool {
+    matches!(c as u32, 0x1100..=0x115F | 0x2E80..=0x303E | 0x3041..=0x33FF | 0x3400..=0x4DBF
+                     | 0x4E00..=0x9FFF | 0xA000..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF
+                     | 0xFE30..=0xFE4F | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6
+                     | 0x20000..=0x2FFFD | 0x30000..=0x3FFFD)
+}
+
+/// number of terminal columns the text takes.
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if is_wide(c) { 2 } else { 1 }).sum()
+}
+
+/// the text padded with spaces to `width` columns, like `{:<width}` does
+/// for single-width text.
+pub fn pad_to_width(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(text));
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+/// the text cut to at most `width` columns, ending with an ellipsis if it
+/// had to be cut.
+pub fn truncate_to_width(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return String::from(text);
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let columns = if is_wide(c) { 2 } else { 1 };
+        if used + columns > width.saturating_sub(1) {
+            break;
+        }
+        truncated.push(c);
+        used += columns;
+    }
+    truncated.push('…');
+    truncated
+}
+
+fn roman_numeral(word: &str) -> Option<u32> {
+    let value = match word {
+        "ii" => 2, "iii" => 3, "iv" => 4, "v" => 5,
+        "vi" => 6, "vii" => 7, "viii" => 8, "ix" => 9, "x" => 10,
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// "2nd", "3rd", "4th" into 2, 3, 4.
+fn ordinal(word: &str) -> Option<u32> {
+    let number = word.strip_suffix("st")
+                     .or_else(|| word.strip_suffix("nd"))
+                     .or_else(|| word.strip_suffix("rd"))
+                     .or_else(|| word.strip_suffix("th"))?;
+    number.parse::<u32>().ok()
+}
+
+/// lowercase the title and reduce it to alphanumeric words separated by a
+/// single space, so that "Re:Zero" and "re_zero" compare equal. Season
+/// markers become a bare number, and the first season loses its marker.
+pub fn normalize_title(title: &str) -> String {
+    let lowered = title.to_lowercase();
+    let words: Vec<&str> = lowered.split(|c: char| !c.is_alphanumeric())
+                                  .filter(|word| !word.is_empty())
+                                  .collect();
+
+    let mut normalized: Vec<String> = Vec::with_capacity(words.len());
+    let mut index = 0;
+    while index < words.len() {
+        let word = words[index];
+        let next = words.get(index + 1).copied();
+        let is_last = index + 1 == words.len();
+
+        let (season, consumed) = match next {
+            Some(number) if word == "season" && number.parse::<u32>().is_ok() => {
+                (number.parse::<u32>().ok(), 2)
+            },
+            Some("season") if ordinal(word).is_some() => (ordinal(word), 2),
+            _ if word.len() > 1 && word.starts_with('s') && word[1..].chars().all(|c| c.is_ascii_digit()) => {
+                (word[1..].parse::<u32>().ok(), 1)
+            },
+            _ if is_last && index > 0 => (roman_numeral(word), 1),
+            _ => (None, 1),
+        };
+
+        match season {
+            Some(1) => {},
+            Some(season) => normalized.push(season.to_string()),
+            None => normalized.push(String::from(word)),
+        }
+        index += if season.is_some() { consumed } else { 1 };
+    }
+    normalized.join(" ")
+}
+
+/// true if the given title is one of the titles of the entry.
+pub fn matches_title(anime: &AnimeAttributes, title: &str) -> bool {
+    let title = normalize_title(title);
+    anime.titles().any(|candidate| normalize_title(candidate) == title)
+}
+
+/// how well the query matches the title, higher is better. None if the
+/// letters of the query do not all appear in the title, in order.
+pub fn fuzzy_score(query: &str, title: &str) -> Option<u32> {
+    let query: Vec<char> = normalize_title(query).chars().collect();
+    if query.is_empty() {
+        return None;
+    }
+    let mut score = 0;
+    let mut matched = 0;
+    let mut previous: Option<char> = None;
+    let mut run = false;
+    for c in normalize_title(title).chars() {
+        if matched < query.len() && c == query[matched] {
+            score += 1;
+            if run {
+                score += 8;
+            }
+            if previous.is_none_or(|previous| previous == ' ') {
+                score += 8;
+            }
+            matched += 1;
+            run = true;
+        } else {
+            run = false;
+        }
+        previous = Some(c);
+    }
+    if matched == query.len() { Some(score) } else { None }
+}
+
+/// the best score of the query across the titles of the entry.
+pub fn search_score(anime: &AnimeAttributes, query: &str) -> Option<u32> {
+    anime.titles().filter_map(|title| fuzzy_score(query, title)).max()
+}
+
+
+#[test]
+fn test_normalize_title() {
+    assert_eq!(normalize_title("Re:Zero"), normalize_title("re_zero"));
+    assert_eq!(normalize_title("Spy x Family Season 2"), "spy x family 2");
+    assert_eq!(normalize_title("Spy x Family 2nd Season"), "spy x family 2");
+    assert_eq!(normalize_title("Spy x Family S2"), "spy x family 2");
+    assert_eq!(normalize_title("Overlord II"), "overlord 2");
+    assert_eq!(normalize_title("Mushishi Season 1"), normalize_title("Mushishi"));
+    // a lone roman numeral-looking word is part of the title.
+    assert_eq!(normalize_title("X"), "x");
+}
+
+#[test]
+fn test_matches_title() {
+    let mut anime = AnimeAttributes::new();
+    anime.title = String::from("Sousou no Frieren");
+    anime.title_eng = String::from("Frieren: Beyond Journey's End");
+    anime.title_jp = String::from("葬送のフリーレン");
+    anime.synonyms = vec![String::from("Frieren at the Funeral")];
+    assert!(matches_title(&anime, "sousou no frieren"));
+    assert!(matches_title(&anime, "葬送のフリーレン"));
+    assert!(matches_title(&anime, "Frieren - Beyond Journey's End"));
+    assert!(matches_title(&anime, "Frieren at the Funeral"));
+    assert!(!matches_title(&anime, "Frieren"));
+}
+
+#[test]
+fn test_fuzzy_score() {
+    assert!(fuzzy_score("frieren", "Sousou no Frieren").is_some());
+    assert!(fuzzy_score("sxf", "Spy x Family").is_some());
+    assert_eq!(fuzzy_score("frieren", "Mushishi"), None);
+    assert_eq!(fuzzy_score("", "Mushishi"), None);
+    // whole words rank above scattered letters.
+    assert!(fuzzy_score("fam", "Spy x Family") > fuzzy_score("fam", "Fullmetal Alchemist"));
+
+    let mut anime = AnimeAttributes::new();
+    anime.title = String::from("Sousou no Frieren");
+    anime.title_eng = String::from("Frieren: Beyond Journey's End");
+    assert_eq!(search_score(&anime, "beyond journey"), fuzzy_score("beyond journey", &anime.title_eng));
+    assert_eq!(search_score(&anime, "kaiju"), None);
+}
+
+#[test]
+fn test_display_width() {
+    assert_eq!(display_width("Frieren"), 7);
+    assert_eq!(display_width("葬送のフリーレン"), 16);
+    assert_eq!(pad_to_width("葬送のフリーレン", 20), "葬送のフリーレン    ");
+    assert_eq!(truncate_to_width("葬送のフリーレン", 9), "葬送のフ…");
+    assert_eq!(truncate_to_width("Frieren", 9), "Frieren");
+}
@@ -0,0 +1,200 @@
+// Where the crawler gets its pages from. Besides talking to the network, a
+// transport can record every response into a fixture directory and replay
+// those fixtures later, which makes offline runs and deterministic parser
+// regression tests against captured pages possible. A dry run only reports
+// what would be fetched to a hook of the caller, which is handy to validate
+// a setup without any outbound traffic. Requests sent over the network are
+// recorded in the audit log, if one is installed. Responses that did not
+// succeed are errors, so callers can retry the transient ones.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+#[cfg(feature = "http")]
+use std::time::Duration;
+
+#[cfg(feature = "http")]
+use crate::audit;
+use crate::error::CrawlerError;
+
+/// hears about every request a dry run does not send, with its url.
+pub type DryRunHook = Arc<dyn Fn(&str) + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub enum Transport {
+    /// fetch every page from the network.
+    #[default]
+    Live,
+    /// fetch from the network and store every response in the directory.
+    Record(PathBuf),
+    /// answer every request from the fixtures stored in the directory.
+    Replay(PathBuf),
+    /// report every request to the hook instead of sending it, and answer
+    /// with an empty body.
+    DryRun(DryRunHook),
+}
+
+impl fmt::Debug for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Transport::Live => write!(f, "Live"),
+            Transport::Record(dir) => f.debug_tuple("Record").field(dir).finish(),
+            Transport::Replay(dir) => f.debug_tuple("Replay").field(dir).finish(),
+            Transport::DryRun(_) => write!(f, "DryRun"),
+        }
+    }
+}
+
+/// name of the fixture file of the given url, e.g.
+/// myanimelist.net_animelist_goksgie_status_1.html
+pub fn fixture_name(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let name: String = without_scheme.trim_end_matches('/')
+                                     .chars()
+                                     .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+                                     .collect();
+    format!("{}.html", name)
+}
+
+fn fixture_path(dir: &Path, url: &str) -> PathBuf {
+    dir.join(fixture_name(url))
+}
+
+fn record_fixture(dir: &Path, url: &str, body: &str) -> Result<(), CrawlerError> {
+    let path = fixture_path(dir, url);
+    fs::create_dir_all(dir).and_then(|_| fs::write(&path, body)).map_err(|err| {
+        CrawlerError::io(format!("could not record {}", path.display()), err)
+    })
+}
+
+/// the error of a response that did not succeed, with the Retry-After of a
+/// 429 or 503 in seconds.
+#[cfg(feature = "http")]
+fn status_error(url: &str, status: reqwest::StatusCode, headers: &reqwest::header::HeaderMap) -> CrawlerError {
+    audit::record(url, Some(status.as_u16()), 0, false);
+    let retry_after = headers.get(reqwest::header::RETRY_AFTER)
+                             .and_then(|value| value.to_str().ok())
+                             .and_then(|value| value.trim().parse::<u64>().ok())
+                             .map(Duration::from_secs);
+    CrawlerError::Status { url: String::from(url), status: status.as_u16(), retry_after }
+}
+
+#[cfg(feature = "http")]
+fn fetch_live(url: &str, headers: &[(&str, &str)]) -> Result<String, CrawlerError> {
+    let mut request = crate::http::client().get(url);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = request.send().map_err(|err| {
+        audit::record(url, None, 0, false);
+        CrawlerError::request(url, err)
+    })?;
+    if !response.status().is_success() {
+        return Err(status_error(url, response.status(), response.headers()));
+    }
+    let status = response.status().as_u16();
+    let body = response.text().map_err(|err| {
+        audit::record(url, Some(status), 0, false);
+        CrawlerError::request(url, err)
+    })?;
+    audit::record(url, Some(status), body.len(), false);
+    Ok(body)
+}
+
+#[cfg(feature = "async")]
+async fn fetch_live_async(url: &str, headers: &[(&str, &str)]) -> Result<String, CrawlerError> {
+    let mut request = crate::http::async_client().get(url);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = request.send().await.map_err(|err| {
+        audit::record(url, None, 0, false);
+        CrawlerError::request(url, err)
+    })?;
+    if !response.status().is_success() {
+        return Err(status_error(url, response.status(), response.headers()));
+    }
+    let status = response.status().as_u16();
+    let body = response.text().await.map_err(|err| {
+        audit::record(url, Some(status), 0, false);
+        CrawlerError::request(url, err)
+    })?;
+    audit::record(url, Some(status), body.len(), false);
+    Ok(body)
+}
+
+#[cfg(not(feature = "http"))]
+fn fetch_live(url: &str, _headers: &[(&str, &str)]) -> Result<String, CrawlerError> {
+    Err(CrawlerError::NetworkDisabled(String::from(url)))
+}
+
+impl Transport {
+    /// GET the given url and return the body of the response.
+    pub fn get(&self, url: &str) -> Result<String, CrawlerError> {
+        self.get_with_headers(url, &[])
+    }
+
+    /// same as get, sending the given headers along. Headers are not part of
+    /// a recorded fixture, so they are never written to disk.
+    pub fn get_with_headers(&self, url: &str, headers: &[(&str, &str)]) -> Result<String, CrawlerError> {
+        match self {
+            Transport::Live => fetch_live(url, headers),
+            Transport::Record(dir) => {
+                let body = fetch_live(url, headers)?;
+                record_fixture(dir, url, &body)?;
+                Ok(body)
+            },
+            Transport::Replay(dir) => {
+                let path = fixture_path(dir, url);
+                fs::read_to_string(&path).map_err(|err| {
+                    CrawlerError::io(format!("no fixture for {} at {}", url, path.display()), err)
+                })
+            },
+            Transport::DryRun(hook) => {
+                hook(url);
+                Ok(String::new())
+            },
+        }
+    }
+
+    /// same as get_with_headers, without blocking the thread while the
+    /// request is in flight. Fixtures are small local files, so replaying
+    /// them is not worth going async for.
+    #[cfg(feature = "async")]
+    pub async fn get_async(&self, url: &str, headers: &[(&str, &str)]) -> Result<String, CrawlerError> {
+        match self {
+            Transport::Live => fetch_live_async(url, headers).await,
+            Transport::Record(dir) => {
+                let body = fetch_live_async(url, headers).await?;
+                record_fixture(dir, url, &body)?;
+                Ok(body)
+            },
+            Transport::Replay(_) | Transport::DryRun(_) => self.get_with_headers(url, headers),
+        }
+    }
+
+    /// true if nothing is actually sent over the network.
+    pub fn is_dry_run(&self) -> bool {
+        matches!(self, Transport::DryRun(_))
+    }
+}
+
+
+#[test]
+fn test_fixture_name() {
+    assert_eq!(fixture_name("https://myanimelist.net/animelist/goksgie?status=1"),
+               "myanimelist.net_animelist_goksgie_status_1.html");
+    assert_eq!(fixture_name("https://myanimelist.net/anime/5081/"),
+               "myanimelist.net_anime_5081.html");
+}
+
+#[test]
+fn test_dry_run_sends_nothing() {
+    let requested = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let hook = Arc::clone(&requested);
+    let transport = Transport::DryRun(Arc::new(move |url: &str| hook.lock().unwrap().push(String::from(url))));
+    assert!(transport.is_dry_run());
+    assert_eq!(transport.get("https://myanimelist.net/anime/5081/").unwrap(), "");
+    assert_eq!(*requested.lock().unwrap(), vec!["https://myanimelist.net/anime/5081/"]);
+}
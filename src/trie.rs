@@ -1,31 +1,45 @@
 // This is a custom trie implementation to register key words for parsing operation.
+//
+// Every word can carry a value, e.g. what a parser should do with the word,
+// so that looking the word up also tells what it stands for. A Trie without
+// values is a plain set of words.
+//
+// A folded trie matches words whatever their case and Unicode form: words
+// and the text looked up are NFKC normalized, then lowercased. Full-width
+// forms Japanese pages turn up read as ASCII ("ａｎｉｍｅ" as "anime"),
+// half-width kana as kana, and decomposed characters match composed ones
+// ("e" followed by a combining acute as "é"). Words are stored folded, so
+// iterating over a folded trie yields the folded words.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::rc::Rc;
-use std::cell::{RefCell, RefMut};
+
+use icu_normalizer::ComposingNormalizerBorrowed;
 
 #[derive(Debug)]
-struct Node {
+struct Node<V> {
     token: char,
-    is_word: bool,
-    children: HashMap<char, Node>,
+    /// set for the nodes that end a registered word.
+    value: Option<V>,
+    children: HashMap<char, Node<V>>,
 }
 
-impl Node {
-    pub fn new(token: char, is_word: bool) -> Self {
-        Node { token, is_word, children: HashMap::new() }
+impl<V> Node<V> {
+    pub fn new(token: char) -> Self {
+        Node { token, value: None, children: HashMap::new() }
     }
 }
 
 #[derive(Debug)]
-pub struct Trie {
-    root: Node,
+pub struct Trie<V = ()> {
+    root: Node<V>,
+    folded: bool,
 }
 
 
 impl Trie {
     pub fn new(op_words: Option<&Vec<&str>>) -> Self {
-        let mut trie = Trie { root: Node::new(' ', false) };
+        let mut trie = Trie::empty();
         match op_words {
             Some(words) => {
                 for word in words.iter() {
@@ -39,31 +53,213 @@ impl Trie {
         }
     }
 
+    /// same as new, the trie matching words whatever their case and
+    /// Unicode form.
+    pub fn new_folded(op_words: Option<&Vec<&str>>) -> Self {
+        let mut trie = Trie::empty_folded();
+        for word in op_words.into_iter().flatten() {
+            trie.insert_word(word);
+        }
+        trie
+    }
+
     pub fn insert_word(&mut self, word: &str) {
-        let mut trie_iter = &mut self.root;
+        self.insert(word, ());
+    }
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Self {
+        Trie::empty()
+    }
+}
+
+impl<V> Trie<V> {
+    /// a trie without words.
+    pub fn empty() -> Self {
+        Trie { root: Node::new(' '), folded: false }
+    }
+
+    /// a folded trie without words.
+    pub fn empty_folded() -> Self {
+        Trie { root: Node::new(' '), folded: true }
+    }
 
-        for (index, c) in word.chars().enumerate() {
-            let iter_children = &mut trie_iter.children;
-            if !iter_children.contains_key(&c) {
-                iter_children.insert(c, Node::new(c, index == word.len() - 1));
+    pub fn is_folded(&self) -> bool {
+        self.folded
+    }
+
+    /// the word as the trie stores it: unchanged, or NFKC normalized and
+    /// lowercased if the trie is folded.
+    pub fn fold<'w>(&self, word: &'w str) -> Cow<'w, str> {
+        if !self.folded {
+            return Cow::Borrowed(word);
+        }
+        Cow::Owned(ComposingNormalizerBorrowed::new_nfkc().normalize(word).to_lowercase())
+    }
+
+    /// end of the piece of the text from `start` that folds on its own: a
+    /// character, along with the combining characters that fold into it,
+    /// e.g. "ｶﾞ" into "ガ".
+    fn piece_end(&self, text: &str, start: usize) -> usize {
+        let mut chars = text[start..].char_indices();
+        let mut end = start + chars.next().map_or(0, |(_, c)| c.len_utf8());
+        if !self.folded {
+            return end;
+        }
+        for (offset, c) in chars {
+            let next = start + offset + c.len_utf8();
+            let apart = format!("{}{}", self.fold(&text[start..end]), self.fold(&text[end..next]));
+            if self.fold(&text[start..next]) == apart {
+                break;
             }
-            trie_iter = iter_children.get_mut(&c).unwrap();
-             
+            end = next;
         }
+        end
+    }
+
+    /// register the word with its value, returning the value it had if it
+    /// was registered before. The empty word cannot be registered.
+    pub fn insert(&mut self, word: &str, value: V) -> Option<V> {
+        if word.is_empty() {
+            return None;
+        }
+        let word = self.fold(word);
+        let mut trie_iter = &mut self.root;
+
+        for c in word.chars() {
+            trie_iter = trie_iter.children.entry(c).or_insert_with(|| Node::new(c));
+        }
+        // the word may end on the path of a longer word inserted before it.
+        trie_iter.value.replace(value)
+    }
+
+    /// the node the given prefix leads to, None if no word starts with it.
+    fn find(&self, prefix: &str) -> Option<&Node<V>> {
+        descend(&self.root, &self.fold(prefix))
+    }
+
+    /// the value of the word, None if it is not registered.
+    pub fn get(&self, word: &str) -> Option<&V> {
+        self.find(word)?.value.as_ref()
     }
 
     pub fn contains_word(&self, word: &str) -> bool {
+        self.get(word).is_some()
+    }
+
+    /// returns true if a registered word starts with the given prefix.
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        // the root is no word, so an empty trie has no word for "".
+        self.find(prefix).is_some_and(|node| node.value.is_some() || !node.children.is_empty())
+    }
+
+    /// the registered words starting with the given prefix, in
+    /// alphabetical order.
+    pub fn words_with_prefix(&self, prefix: &str) -> Vec<String> {
+        match self.find(prefix) {
+            Some(node) => Words(Entries::from(node, self.fold(prefix).into_owned())).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// iterates over the registered words in alphabetical order.
+    pub fn iter(&self) -> Words<'_, V> {
+        Words(self.entries())
+    }
+
+    /// iterates over the registered words and their values in alphabetical
+    /// order of the words.
+    pub fn entries(&self) -> Entries<'_, V> {
+        Entries::from(&self.root, String::new())
+    }
+
+    /// returns the length in bytes of the longest registered word that is
+    /// a prefix of the given text.
+    pub fn longest_match(&self, text: &str) -> Option<usize> {
         let mut trie_iter = &self.root;
+        let mut longest = None;
 
-        for c in word.chars() {
-            let iter_children = &trie_iter.children;
-            if iter_children.contains_key(&c) {
-                trie_iter = iter_children.get(&c).unwrap(); 
-            } else {
-                return false;
+        // lengths are of the text as given, however long its folded form.
+        let mut start = 0;
+        while start < text.len() {
+            let end = self.piece_end(text, start);
+            match descend(trie_iter, &self.fold(&text[start..end])) {
+                Some(child) => {
+                    trie_iter = child;
+                    if trie_iter.value.is_some() {
+                        longest = Some(end);
+                    }
+                },
+                None => break,
             }
+            start = end;
         }
-        trie_iter.is_word
+        longest
+    }
+}
+
+/// the node the already folded text leads to from the given node.
+fn descend<'a, V>(node: &'a Node<V>, text: &str) -> Option<&'a Node<V>> {
+    let mut trie_iter = node;
+
+    for c in text.chars() {
+        trie_iter = trie_iter.children.get(&c)?;
+    }
+    Some(trie_iter)
+}
+
+/// Iterator over the words of a trie and their values, see Trie::entries.
+#[derive(Debug)]
+pub struct Entries<'a, V> {
+    // nodes still to visit with the word leading to them, the next one last.
+    stack: Vec<(&'a Node<V>, String)>,
+}
+
+impl<'a, V> Entries<'a, V> {
+    fn from(node: &'a Node<V>, word: String) -> Self {
+        Entries { stack: vec![(node, word)] }
+    }
+}
+
+impl<'a, V> Iterator for Entries<'a, V> {
+    type Item = (String, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, word)) = self.stack.pop() {
+            let mut children: Vec<&Node<V>> = node.children.values().collect();
+            children.sort_by_key(|child| std::cmp::Reverse(child.token));
+            for child in children {
+                let mut child_word = word.clone();
+                child_word.push(child.token);
+                self.stack.push((child, child_word));
+            }
+            if let Some(value) = &node.value {
+                return Some((word, value));
+            }
+        }
+        None
+    }
+}
+
+/// Iterator over the words of a trie, see Trie::iter.
+#[derive(Debug)]
+pub struct Words<'a, V = ()>(Entries<'a, V>);
+
+impl<'a, V> Iterator for Words<'a, V> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.0.next().map(|(word, _)| word)
+    }
+}
+
+impl<'a, V> IntoIterator for &'a Trie<V> {
+    type Item = String;
+    type IntoIter = Words<'a, V>;
+
+    fn into_iter(self) -> Words<'a, V> {
+        self.iter()
     }
 }
 
@@ -72,8 +268,8 @@ impl Trie {
 fn test_single_insert() {
     let mut trie = Trie::new(None);
     trie.insert_word("status");
-    assert_eq!(trie.contains_word("status"), true);
-    assert_eq!(trie.contains_word("status1"), false);
+    assert!(trie.contains_word("status"));
+    assert!(!trie.contains_word("status1"));
 }
 
 #[test]
@@ -83,15 +279,93 @@ fn test_multiple_insert() {
                      "anime_start_date_string", "anime_num_episodes"];
     let mut trie = Trie::new(Some(&words));
     for word in words.iter() {
-        trie.insert_word(&word);
+        trie.insert_word(word);
     }
 
     for word in words.iter() {
-        assert_eq!(trie.contains_word(&word), true);
+        assert!(trie.contains_word(word));
     }
 
-    assert_eq!(trie.contains_word("start_date_stringg"), false);
-    assert_eq!(trie.contains_word("start_date_strin"), false);
-    assert_eq!(trie.contains_word("start_"), false);
-    assert_eq!(trie.contains_word("anime_airing_status"), true);
+    assert!(!trie.contains_word("start_date_stringg"));
+    assert!(!trie.contains_word("start_date_strin"));
+    assert!(!trie.contains_word("start_"));
+    assert!(trie.contains_word("anime_airing_status"));
+}
+
+#[test]
+fn test_longest_match() {
+    let words = vec!["anime_title", "anime_title_eng"];
+    let trie = Trie::new(Some(&words));
+    assert_eq!(trie.longest_match("anime_title_eng&quot;:"), Some(15));
+    assert_eq!(trie.longest_match("anime_title&quot;:"), Some(11));
+    assert_eq!(trie.longest_match("anime_tit"), None);
+}
+
+#[test]
+fn test_prefix_search() {
+    let words = vec!["anime_title_eng", "anime_title", "anime_id", "score", "進撃"];
+    let trie = Trie::new(Some(&words));
+    assert!(trie.contains_word("anime_title"));
+    assert!(trie.starts_with("anime_ti"));
+    assert!(trie.starts_with("score"));
+    assert!(!trie.starts_with("scores"));
+    assert!(trie.starts_with("進"));
+    assert_eq!(trie.words_with_prefix("anime_t"), vec!["anime_title", "anime_title_eng"]);
+    assert_eq!(trie.words_with_prefix("status"), Vec::<String>::new());
+    assert_eq!(trie.iter().collect::<Vec<_>>(),
+               vec!["anime_id", "anime_title", "anime_title_eng", "score", "進撃"]);
+    assert!(!Trie::new(None).starts_with(""));
+}
+
+#[test]
+fn test_values() {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Kind {
+        Title,
+        Count,
+    }
+
+    let mut trie: Trie<Kind> = Trie::empty();
+    assert_eq!(trie.insert("anime_title", Kind::Title), None);
+    trie.insert("anime_title_eng", Kind::Title);
+    assert_eq!(trie.insert("anime_num_episodes", Kind::Title), None);
+    assert_eq!(trie.insert("anime_num_episodes", Kind::Count), Some(Kind::Title));
+    assert_eq!(trie.insert("", Kind::Count), None);
+    assert_eq!(trie.get("anime_title"), Some(&Kind::Title));
+    assert_eq!(trie.get("anime_num_episodes"), Some(&Kind::Count));
+    assert_eq!(trie.get("anime_"), None);
+    assert!(!trie.contains_word(""));
+    assert_eq!(trie.entries().map(|(word, kind)| (word, *kind)).collect::<Vec<_>>(),
+               vec![(String::from("anime_num_episodes"), Kind::Count), (String::from("anime_title"), Kind::Title),
+                    (String::from("anime_title_eng"), Kind::Title)]);
+}
+
+#[test]
+fn test_folded() {
+    let words = vec!["anime_title", "anime_title_eng"];
+    let trie = Trie::new_folded(Some(&words));
+    assert!(trie.contains_word("Anime_Title"));
+    assert!(trie.contains_word("ＡＮＩＭＥ＿ＴＩＴＬＥ"));
+    assert!(trie.starts_with("ANIME_T"));
+    assert_eq!(trie.words_with_prefix("Anime_title_"), vec!["anime_title_eng"]);
+    // the length is of the full-width text, three bytes per character.
+    assert_eq!(trie.longest_match("ａｎｉｍｅ_title&quot;:"), Some(5 * 3 + 6));
+    assert_eq!(trie.fold("Ｓcore"), "score");
+
+    // decomposed characters match their composed form, and the other way.
+    let words = vec!["café", "ｶﾞﾝﾀﾞﾑ"];
+    let trie = Trie::new_folded(Some(&words));
+    assert!(trie.contains_word("cafe\u{301}"));
+    assert!(trie.contains_word("CAFÉ"));
+    assert!(trie.contains_word("ガンダム"));
+    assert_eq!(trie.iter().collect::<Vec<_>>(), vec!["café", "ガンダム"]);
+    assert_eq!(trie.longest_match("cafe\u{301} au lait"), Some(6));
+    assert_eq!(trie.longest_match("ｶﾞﾝﾀﾞﾑ!"), Some(6 * 3));
+    assert_eq!(trie.longest_match("cafe"), None);
+
+    let mut trie: Trie<i32> = Trie::empty_folded();
+    trie.insert("Score", 1);
+    assert_eq!(trie.insert("SCORE", 2), Some(1));
+    assert_eq!(trie.iter().collect::<Vec<_>>(), vec!["score"]);
+    assert!(!Trie::new(Some(&words)).contains_word("Anime_Title"));
 }
@@ -0,0 +1,98 @@
+// Watch mode: the list is crawled again every few minutes and every crawl is
+// compared with the previous one, so the user hears about an entry once on
+// the day it airs and whenever its episode count changes, without rerunning
+// the crawler.
+// The events are returned to the caller, which decides how to tell the user.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use chrono::NaiveDate;
+
+use crate::anime::AnimeAttributes;
+use crate::titles::TitleLanguage;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchEvent {
+    /// the entry airs today.
+    AiringToday { title: String },
+    /// MAL lists a different number of episodes than at the last crawl.
+    EpisodeCount { title: String, before: i32, after: i32 },
+}
+
+impl fmt::Display for WatchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WatchEvent::AiringToday { title } => write!(f, "{} airs today", title),
+            WatchEvent::EpisodeCount { title, before: 0, after } => {
+                write!(f, "{} now has {} episodes", title, after)
+            },
+            WatchEvent::EpisodeCount { title, before, after } => {
+                write!(f, "{} now has {} episodes instead of {}", title, after, before)
+            },
+        }
+    }
+}
+
+/// what the previous crawls of the watched list found.
+#[derive(Debug, Default)]
+pub struct Watcher {
+    language: TitleLanguage,
+    episodes: HashMap<i32, i32>,
+    announced: HashSet<(i32, NaiveDate)>,
+}
+
+impl Watcher {
+    /// events name entries by their title in `language`.
+    pub fn new(language: TitleLanguage) -> Self {
+        Watcher { language, ..Default::default() }
+    }
+
+    /// the events of a new crawl of the list. Airing entries are announced
+    /// once per day, episode counts are compared from the second crawl on.
+    pub fn update(&mut self, entries: &[AnimeAttributes], today: NaiveDate) -> Vec<WatchEvent> {
+        let mut events = Vec::new();
+        for anime in entries {
+            let title = String::from(anime.preferred_title(self.language));
+            if anime.is_airing_today() && self.announced.insert((anime.id, today)) {
+                events.push(WatchEvent::AiringToday { title: title.clone() });
+            }
+            match self.episodes.insert(anime.id, anime.num_episodes) {
+                Some(before) if before != anime.num_episodes => {
+                    events.push(WatchEvent::EpisodeCount { title, before, after: anime.num_episodes });
+                },
+                _ => {}
+            }
+        }
+        self.announced.retain(|(_, day)| *day == today);
+        events
+    }
+}
+
+
+#[test]
+fn test_watcher_events() {
+    use chrono::Weekday;
+
+    let mut watcher = Watcher::new(TitleLanguage::Romaji);
+    let mut frieren = AnimeAttributes::new();
+    frieren.id = 52991;
+    frieren.title = String::from("Sousou no Frieren");
+    frieren.is_airing = true;
+    frieren.set_airing_weekday(Weekday::Mon);
+    frieren.set_today(Weekday::Mon);
+    let mut mushishi = AnimeAttributes::new();
+    mushishi.id = 457;
+    mushishi.title = String::from("Mushishi");
+
+    let monday = NaiveDate::from_ymd_opt(2023, 10, 2).unwrap();
+    let events = watcher.update(&[frieren.clone(), mushishi.clone()], monday);
+    assert_eq!(events, vec![WatchEvent::AiringToday { title: String::from("Sousou no Frieren") }]);
+    // announced once per day.
+    assert!(watcher.update(&[frieren.clone(), mushishi.clone()], monday).is_empty());
+
+    frieren.num_episodes = 28;
+    let events = watcher.update(&[frieren.clone(), mushishi], monday.succ_opt().unwrap());
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1].to_string(), "Sousou no Frieren now has 28 episodes");
+}
@@ -0,0 +1,92 @@
+// A stand-in for MAL in integration tests: an HTTP server on a local port
+// answering requests with the pages of fixtures/, so that crawls pointed at
+// it with CrawlOptions::base_url run end to end without the network.
+// Paths without a fixture are answered with 404.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anime_crawler::http::{self, HttpConfig};
+use anime_crawler::config::ProxyConfig;
+
+#[derive(Clone)]
+struct Route {
+    status: u16,
+    body: String,
+}
+
+pub struct MockMal {
+    base_url: String,
+    routes: Arc<Mutex<HashMap<String, Route>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockMal {
+    /// a server answering nothing but 404 until routes are added.
+    pub fn start() -> Self {
+        // requests to the server must not go through a proxy of the
+        // environment. The first configuration of the process wins, which
+        // is this one for every test.
+        let _ = http::configure(HttpConfig { proxy: ProxyConfig::Direct, ..Default::default() });
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("could not bind the mock server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let routes: Arc<Mutex<HashMap<String, Route>>> = Default::default();
+        let requests: Arc<Mutex<Vec<String>>> = Default::default();
+        let (server_routes, server_requests) = (Arc::clone(&routes), Arc::clone(&requests));
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (routes, requests) = (Arc::clone(&server_routes), Arc::clone(&server_requests));
+                thread::spawn(move || serve(stream, &routes, &requests));
+            }
+        });
+        MockMal { base_url, routes, requests }
+    }
+
+    /// url the crawl is pointed at, e.g. http://127.0.0.1:41234.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// answer requests for the path, e.g. "/anime/52991/", with the body.
+    pub fn route(&self, path: &str, status: u16, body: &str) -> &Self {
+        self.routes.lock().unwrap().insert(String::from(path), Route { status, body: String::from(body) });
+        self
+    }
+
+    /// answer requests for the path with the fixture of that name.
+    pub fn fixture(&self, path: &str, name: &str) -> &Self {
+        let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(name);
+        let body = std::fs::read_to_string(&file).unwrap_or_else(|err| panic!("{}: {}", file.display(), err));
+        self.route(path, 200, &body)
+    }
+
+    /// paths requested so far, in the order they came in.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn serve(mut stream: TcpStream, routes: &Mutex<HashMap<String, Route>>, requests: &Mutex<Vec<String>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    // the headers are not looked at, only read past.
+    let mut header = String::new();
+    while reader.read_line(&mut header).is_ok_and(|read| read > 2) {
+        header.clear();
+    }
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+    requests.lock().unwrap().push(path.clone());
+    let route = routes.lock().unwrap().get(&path).cloned()
+                      .unwrap_or(Route { status: 404, body: String::from("Not Found") });
+    let response = format!("HTTP/1.1 {} Mock\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\
+                            Connection: close\r\n\r\n{}", route.status, route.body.len(), route.body);
+    let _ = stream.write_all(response.as_bytes());
+}
@@ -0,0 +1,78 @@
+// Crawls against a local server serving the fixtures, see common::MockMal.
+#![cfg(feature = "http")]
+
+mod common;
+
+use std::sync::Arc;
+
+use chrono::NaiveTime;
+
+use anime_crawler::config::REGISTERED_WORDS;
+use anime_crawler::requester::{self, AnimePageCache, CrawlOptions};
+use anime_crawler::trie::Trie;
+use anime_crawler::{Client, CrawlerError, UserAttributes};
+
+use common::MockMal;
+
+fn options(mal: &MockMal) -> CrawlOptions {
+    CrawlOptions { base_url: Some(String::from(mal.base_url())), time_diff_to_jst: Some(0), ..Default::default() }
+}
+
+#[test]
+fn test_crawl_animelist() {
+    let mal = MockMal::start();
+    mal.fixture("/robots.txt", "myanimelist.net_robots.txt.html")
+       .fixture("/animelist/fixture?status=1", "myanimelist.net_animelist_fixture_status_1.html")
+       .fixture("/anime/52991/", "myanimelist.net_anime_52991.html");
+    let trie = Trie::new(Some(&REGISTERED_WORDS.to_vec()));
+    let user = UserAttributes::new(String::from("fixture"));
+    let options = CrawlOptions { broadcast_times: true, ..options(&mal) };
+
+    let result = requester::crawl(&user, &trie, &options).unwrap();
+    let titles: Vec<&str> = result.entries.iter().map(|anime| anime.title.as_str()).collect();
+    assert_eq!(titles, vec!["Sousou no Frieren", "Mushishi"]);
+    assert_eq!(result.entries[0].airing_time, NaiveTime::from_hms_opt(23, 0, 0));
+    assert!(result.report.is_clean(), "{:?}", result.report);
+    assert_eq!(mal.requests(), vec!["/robots.txt", "/animelist/fixture?status=1", "/anime/52991/"]);
+}
+
+#[test]
+fn test_shared_anime_pages() {
+    let mal = MockMal::start();
+    mal.fixture("/animelist/fixture?status=1", "myanimelist.net_animelist_fixture_status_1.html")
+       .fixture("/animelist/friend?status=1", "myanimelist.net_animelist_fixture_status_1.html")
+       .fixture("/anime/52991/", "myanimelist.net_anime_52991.html");
+    let trie = Trie::new(Some(&REGISTERED_WORDS.to_vec()));
+    let users = [UserAttributes::new(String::from("fixture")), UserAttributes::new(String::from("friend"))];
+    let page_cache = Arc::new(AnimePageCache::new());
+    let options = CrawlOptions { broadcast_times: true, ignore_robots: true, page_cache: Some(Arc::clone(&page_cache)),
+                                 ..options(&mal) };
+
+    for list in requester::get_animelists(&users, &trie, &options) {
+        assert_eq!(list.unwrap()[0].airing_time, NaiveTime::from_hms_opt(23, 0, 0));
+    }
+    // Frieren is on both lists, its page is fetched for one of them only.
+    assert_eq!((page_cache.len(), page_cache.hits(), page_cache.misses()), (1, 1, 1));
+    assert_eq!(mal.requests().iter().filter(|path| *path == "/anime/52991/").count(), 1);
+}
+
+#[test]
+fn test_missing_user() {
+    let mal = MockMal::start();
+    let user = UserAttributes::new(String::from("nobody"));
+    let client = Client::builder().base_url(mal.base_url()).build();
+    assert!(matches!(client.crawl(&user), Err(CrawlerError::UserNotFound(name)) if name == "nobody"));
+}
+
+#[test]
+fn test_anime_details() {
+    let mal = MockMal::start();
+    mal.fixture("/anime/52991/", "myanimelist.net_anime_52991.html");
+    let options = CrawlOptions { ignore_robots: true, ..options(&mal) };
+
+    let details = requester::get_anime_details(52991, &options).unwrap();
+    assert_eq!(details.broadcast.as_deref(), Some("Fridays at 23:00 (JST)"));
+    assert_eq!(details.episode_seconds, Some(24 * 60));
+    assert!(matches!(requester::get_anime_details(457, &options), Err(CrawlerError::Status { status: 404, .. })));
+    assert_eq!(mal.requests(), vec!["/anime/52991/", "/anime/457/"]);
+}